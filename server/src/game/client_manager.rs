@@ -1,13 +1,13 @@
-use super::{GameData, GameLobbyEvent};
+use super::{visibility, GameData, GameLobbyEvent};
 use crate::util::{generate_id, send_logging, WsReceiver, WsSender};
-use futures::{SinkExt, StreamExt};
+use futures::{future, SinkExt, StreamExt};
 use std::{collections::HashMap, fmt::Debug};
 use tokio::{
     select,
     sync::{mpsc, oneshot},
 };
 use werewolf_rs::{
-    game::{GameInfo, PlayerInfo, RoleData, RoleInfo},
+    game::{GameConfig, GameInfo, LobbyPhase},
     packet::{
         InteractionFollowup, InteractionRequest, InteractionResponse, PacketToClient,
         PacketToServer,
@@ -15,32 +15,67 @@ use werewolf_rs::{
     util::{InteractionId, LobbyId, PlayerId},
 };
 
+/*
+The events that the creator of an interaction receives from the clients taking part in it
+*/
+#[derive(Clone, Debug)]
+pub enum InteractionEvent {
+    Response(InteractionResponse),
+    //The client lost its connection and won't respond until it reconnects
+    Disconnected,
+    Reconnected,
+}
+
+pub type InteractionSender = mpsc::Sender<(PlayerId, InteractionEvent)>;
+
 pub enum ClientEvent {
-    SendUpdate(GameData),
+    SendUpdate(GameData, GameConfig, LobbyPhase),
     /*Create an interaction and send the packet to the client.
     The interaction ID is sent back over the provided oneshot channel*/
     CreateInteraction(
         InteractionRequest,
-        mpsc::Sender<(PlayerId, InteractionResponse)>,
+        InteractionSender,
         oneshot::Sender<InteractionId>,
     ),
     FollowupInteraction(InteractionId, InteractionFollowup),
     CloseInteraction(InteractionId),
+    //Close all interactions that are still open, for example because the game has been cancelled
+    CloseAllInteractions,
     SendPacket(PacketToClient),
+    //Replace the connection to the client with a new websocket and resend the current state
+    Reconnect {
+        ws_read: WsReceiver,
+        ws_write: WsSender,
+    },
+}
+
+/*
+An interaction that has not been closed yet.
+Everything that has been sent is kept, so that it can be sent again to a reconnecting client
+*/
+struct OpenInteraction {
+    response_channel: InteractionSender,
+    request: InteractionRequest,
+    followups: Vec<InteractionFollowup>,
 }
 
 /*
 A struct that manages the connection to one client in a game lobby.
 It manages asynchronously sending and receiving packets from the client (using 2 additional tasks).
+The manager outlives the connection, so that the client can reconnect to it later on
 */
 pub struct ClientManager {
-    packet_send: mpsc::Sender<PacketToClient>,
-    packet_receive: mpsc::Receiver<PacketToServer>,
+    //Both channels are None while the client is disconnected
+    packet_send: Option<mpsc::Sender<PacketToClient>>,
+    packet_receive: Option<mpsc::Receiver<PacketToServer>>,
     event_receive: mpsc::Receiver<ClientEvent>,
     game_lobby_send: mpsc::Sender<GameLobbyEvent>,
 
+    lobby_id: LobbyId,
     client_id: PlayerId,
-    interactions: HashMap<InteractionId, mpsc::Sender<(PlayerId, InteractionResponse)>>,
+    reconnect_token: String,
+    interactions: HashMap<InteractionId, OpenInteraction>,
+    last_game_info: Option<GameInfo>,
 }
 
 impl ClientManager {
@@ -50,11 +85,31 @@ impl ClientManager {
     pub async fn new(
         lobby_id: LobbyId,
         client_id: PlayerId,
-        mut ws_send: WsSender,
-        mut ws_rec: WsReceiver,
+        reconnect_token: String,
+        ws_send: WsSender,
+        ws_rec: WsReceiver,
         game_lobby_send: mpsc::Sender<GameLobbyEvent>,
     ) -> (Self, mpsc::Sender<ClientEvent>) {
         let (event_sender, event_receiver) = mpsc::channel(8);
+        let mut client_manager = ClientManager {
+            event_receive: event_receiver,
+            packet_receive: None,
+            packet_send: None,
+            game_lobby_send,
+            lobby_id,
+            client_id,
+            reconnect_token,
+            interactions: HashMap::new(),
+            last_game_info: None,
+        };
+        client_manager.connect(ws_send, ws_rec);
+        (client_manager, event_sender)
+    }
+
+    /*
+    Starts the daemons for a (new) websocket connection, replacing the old connection if there is one
+    */
+    fn connect(&mut self, mut ws_send: WsSender, mut ws_rec: WsReceiver) {
         let (packet_send, mut packet_send_listener) = mpsc::channel(8);
         let (packet_receive_writer, packet_receive) = mpsc::channel(8);
         //The websocket receiving daemon
@@ -67,15 +122,13 @@ impl ClientManager {
             }
         });
         //The websocket sending daemon
+        let joined_packet = PacketToClient::JoinedLobby {
+            lobby_id: self.lobby_id,
+            client_id: self.client_id,
+            reconnect_token: self.reconnect_token.clone(),
+        };
         tokio::spawn(async move {
-            send_logging(
-                &mut ws_send,
-                PacketToClient::JoinedLobby {
-                    lobby_id,
-                    client_id,
-                },
-            )
-            .await;
+            send_logging(&mut ws_send, joined_packet).await;
             while let Some(packet) = packet_send_listener.recv().await {
                 match packet {
                     PacketToClient::CloseConnection => {
@@ -90,17 +143,95 @@ impl ClientManager {
                 }
             }
         });
-        (
-            ClientManager {
-                event_receive: event_receiver,
-                packet_receive,
-                packet_send,
-                game_lobby_send,
-                client_id,
-                interactions: HashMap::new(),
-            },
-            event_sender,
-        )
+        if let Some(old_packet_send) = self.packet_send.replace(packet_send) {
+            //The client connected from somewhere else, so the old websocket is closed
+            old_packet_send
+                .try_send(PacketToClient::CloseConnection)
+                .ok();
+        }
+        self.packet_receive = Some(packet_receive);
+    }
+
+    /*
+    Drops the current connection and notifies the game lobby and all open interactions about it
+    */
+    async fn disconnect(&mut self) {
+        self.packet_send = None;
+        self.packet_receive = None;
+        self.notify_interactions(InteractionEvent::Disconnected)
+            .await;
+        self.game_lobby_send
+            .send(GameLobbyEvent::ConnectionLost {
+                client_id: self.client_id,
+            })
+            .await
+            .unwrap();
+    }
+
+    /*
+    Sends a connection change to the creators of all open interactions
+    */
+    async fn notify_interactions(&self, event: InteractionEvent) {
+        for interaction in self.interactions.values() {
+            if interaction
+                .response_channel
+                .send((self.client_id, event.clone()))
+                .await
+                .is_err()
+            {
+                //The creator of the interaction has already stopped listening
+                warn!("Unable to notify interaction about connection change");
+            }
+        }
+    }
+
+    /*
+    Sends a packet to the client. Packets are dropped while the client is disconnected
+    */
+    async fn send_packet(&mut self, packet: PacketToClient) {
+        if let Some(packet_send) = &self.packet_send {
+            if packet_send.send(packet).await.is_err() {
+                error!("Error sending packet to the websocket sending daemon");
+            }
+        }
+    }
+
+    /*
+    Resends the last game info and all open interactions, for example after a reconnect
+    */
+    async fn resend_state(&mut self) {
+        if let Some(game_info) = self.last_game_info.clone() {
+            self.send_packet(PacketToClient::GameUpdate(game_info))
+                .await;
+        }
+        let mut packets = Vec::new();
+        for (interaction_id, interaction) in self.interactions.iter() {
+            packets.push(PacketToClient::InteractionRequest {
+                interaction_id: *interaction_id,
+                data: interaction.request.clone(),
+            });
+            for followup in interaction.followups.iter() {
+                packets.push(PacketToClient::InteractionFollowup {
+                    interaction_id: *interaction_id,
+                    data: followup.clone(),
+                });
+            }
+        }
+        for packet in packets {
+            self.send_packet(packet).await;
+        }
+    }
+
+    /*
+    Waits for the next packet of the client. This never returns while the client is disconnected
+    */
+    async fn receive_packet(
+        packet_receive: &mut Option<mpsc::Receiver<PacketToServer>>,
+    ) -> Option<PacketToServer> {
+        match packet_receive {
+            Some(packet_receive) => packet_receive.recv().await,
+            None => future::pending().await,
+        }
     }
 
     /*
@@ -123,78 +254,84 @@ impl ClientManager {
                         }
                         Some(event) => {
                             match event {
-                                ClientEvent::SendUpdate(game_data) => {
-                                    let player_infos: HashMap<PlayerId, PlayerInfo> = game_data.players
-                                    .into_iter()
-                                    .map(|(id, player)| {
-                                        if id==self.client_id {
-                                            (id, PlayerInfo {
-                                                role_info: RoleInfo::KnownData(player.role_data),
-                                                is_alive: player.is_alive,
-                                                is_lobby_host: player.is_lobby_host
-                                            })
-                                        } else {
-                                            (id, PlayerInfo {
-                                                role_info: match player.role_data {
-                                                    RoleData::Spectator => RoleInfo::KnownData(player.role_data),
-                                                    _ => RoleInfo::Unknown
-                                                },
-                                                is_alive: player.is_alive,
-                                                is_lobby_host: player.is_lobby_host,
-                                            })
-                                        }
-                                    }).collect();
-                                    let game_info = GameInfo {
-                                        players: player_infos
-                                    };
-                                    self.packet_send.send(PacketToClient::GameUpdate(game_info)).await.unwrap();
+                                ClientEvent::SendUpdate(game_data, config, phase) => {
+                                    let game_info = visibility::game_info(self.client_id, &game_data, config, phase);
+                                    self.last_game_info = Some(game_info.clone());
+                                    self.send_packet(PacketToClient::GameUpdate(game_info)).await;
                                 },
                                 ClientEvent::CreateInteraction(data, response_channel, id_oneshot) => {
                                     let interaction_id = generate_id(&self.interactions);
-                                    self.interactions.insert(interaction_id, response_channel);
+                                    self.interactions.insert(interaction_id, OpenInteraction {
+                                        response_channel,
+                                        request: data.clone(),
+                                        followups: Vec::new()
+                                    });
                                     id_oneshot.send(interaction_id).ok();
-                                    self.packet_send.send(PacketToClient::InteractionRequest {
+                                    if self.packet_send.is_none() {
+                                        //The creator has to know that this client won't respond for now
+                                        let interaction = &self.interactions[&interaction_id];
+                                        if interaction.response_channel.send((self.client_id, InteractionEvent::Disconnected)).await.is_err() {
+                                            error!("Unable to notify interaction about disconnected client");
+                                        }
+                                    }
+                                    self.send_packet(PacketToClient::InteractionRequest {
                                         interaction_id,
                                         data
-                                    }).await.unwrap();
+                                    }).await;
                                 }
                                 ClientEvent::FollowupInteraction(interaction_id, data) => {
-                                    self.packet_send.send(PacketToClient::InteractionFollowup {
+                                    if let Some(interaction) = self.interactions.get_mut(&interaction_id) {
+                                        interaction.followups.push(data.clone());
+                                    }
+                                    self.send_packet(PacketToClient::InteractionFollowup {
                                         interaction_id,
                                         data
-                                    }).await.unwrap();
+                                    }).await;
                                 }
                                 ClientEvent::CloseInteraction(interaction_id) => {
-                                    self.packet_send.send(PacketToClient::InteractionClose { interaction_id }).await.unwrap();
+                                    self.interactions.remove(&interaction_id);
+                                    self.send_packet(PacketToClient::InteractionClose { interaction_id }).await;
+                                }
+                                ClientEvent::CloseAllInteractions => {
+                                    let interaction_ids: Vec<InteractionId> = self.interactions.drain().map(|(id, _)| id).collect();
+                                    for interaction_id in interaction_ids {
+                                        self.send_packet(PacketToClient::InteractionClose { interaction_id }).await;
+                                    }
                                 }
                                 ClientEvent::SendPacket(packet) => {
-                                    self.packet_send.send(packet).await.unwrap();
+                                    self.send_packet(packet).await;
+                                }
+                                ClientEvent::Reconnect { ws_read, ws_write } => {
+                                    let was_disconnected = self.packet_send.is_none();
+                                    self.connect(ws_write, ws_read);
+                                    self.resend_state().await;
+                                    if was_disconnected {
+                                        self.notify_interactions(InteractionEvent::Reconnected).await;
+                                    }
                                 }
                             }
                         }
                     }
                 }
                 //Receive a packet from the client and handle it
-                packet = self.packet_receive.recv() => {
+                packet = Self::receive_packet(&mut self.packet_receive) => {
                     match packet {
                         None => {
-                            //Notify the game lobby that the client lost its connection
-                            self.game_lobby_send.send(GameLobbyEvent::ConnectionLost { client_id: self.client_id }).await.unwrap();
-                            return;
+                            //Notify the game lobby that the client lost its connection. The client may reconnect later on
+                            self.disconnect().await;
                         }
                         Some(packet) => {
                             match packet {
                                 PacketToServer::CloseConnection => {
-                                    self.game_lobby_send.send(GameLobbyEvent::ConnectionLost { client_id: self.client_id }).await.unwrap();
-                                    return;
+                                    self.disconnect().await;
                                 }
                                 PacketToServer::InteractionResponse { interaction_id, data } => {
                                     match self.interactions.get(&interaction_id) {
                                         None => {
                                             warn!("Received interaction response with unknown id: {:?}", interaction_id);
                                         }
-                                        Some(channel) => {
-                                            if let Err(e) = channel.send((self.client_id, data)).await {
+                                        Some(interaction) => {
+                                            if let Err(e) = interaction.response_channel.send((self.client_id, InteractionEvent::Response(data))).await {
                                                 error!("Unable to send back interaction response: {:?}", e);
                                             }
                                         }
@@ -203,7 +340,19 @@ impl ClientManager {
                                 PacketToServer::StartGame => {
                                     self.game_lobby_send.send(GameLobbyEvent::StartGame { requested_by: self.client_id} ).await.unwrap();
                                 }
-                                PacketToServer::Unknown | PacketToServer::JoinLobby(_) | PacketToServer::CreateNewLobby => {
+                                PacketToServer::CancelGame => {
+                                    self.game_lobby_send.send(GameLobbyEvent::CancelGame { requested_by: self.client_id }).await.unwrap();
+                                }
+                                PacketToServer::ReturnToLobby => {
+                                    self.game_lobby_send.send(GameLobbyEvent::ReturnToLobby { requested_by: self.client_id }).await.unwrap();
+                                }
+                                PacketToServer::UpdateGameConfig(config) => {
+                                    self.game_lobby_send.send(GameLobbyEvent::UpdateGameConfig { config, requested_by: self.client_id }).await.unwrap();
+                                }
+                                PacketToServer::SetName(name) => {
+                                    self.game_lobby_send.send(GameLobbyEvent::SetName { name, requested_by: self.client_id }).await.unwrap();
+                                }
+                                PacketToServer::Unknown | PacketToServer::JoinLobby { .. } | PacketToServer::CreateNewLobby { .. } | PacketToServer::Reconnect { .. } => {
                                     warn!("Received unknown/invalid packet from client in game lobby");
                                 }
                             }
@@ -218,11 +367,13 @@ impl ClientManager {
 impl Debug for ClientEvent {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::SendUpdate(_) => write!(f, "SendUpdate"),
+            Self::SendUpdate(_, _, _) => write!(f, "SendUpdate"),
             Self::CreateInteraction(_, _, _) => write!(f, "CreateInteraction"),
             Self::FollowupInteraction(_, _) => write!(f, "FollowupInteraction"),
             Self::CloseInteraction(_) => write!(f, "CloseInteraction"),
+            Self::CloseAllInteractions => write!(f, "CloseAllInteractions"),
             Self::SendPacket(packet) => write!(f, "SendPacket({:?}", packet),
+            Self::Reconnect { .. } => write!(f, "Reconnect"),
         }
     }
 }
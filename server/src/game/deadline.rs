@@ -0,0 +1,87 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures::future;
+use tokio::{sync::mpsc, time::Instant};
+
+/*
+The point in time at which a phase of the game ends.
+The server uses tokio's clock for the timing, while clients are sent the corresponding unix timestamp
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct Deadline {
+    instant: Instant,
+    timestamp: u64,
+}
+
+impl Deadline {
+    /*
+    Creates a deadline that is the given number of seconds from now, or None if the phase has no time limit
+    */
+    pub fn from_seconds(seconds: Option<u64>) -> Option<Self> {
+        let duration = Duration::from_secs(seconds?);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Some(Deadline {
+            instant: Instant::now() + duration,
+            timestamp: (now + duration).as_millis() as u64,
+        })
+    }
+
+    /*
+    The deadline as a unix timestamp in milliseconds, as it is sent to the clients
+    */
+    pub fn timestamp(deadline: &Option<Self>) -> Option<u64> {
+        deadline.map(|deadline| deadline.timestamp)
+    }
+
+    /*
+    Receives the next message from the channel, unless the deadline passes first.
+    Returns None if the deadline has passed or the channel is closed
+    */
+    pub async fn recv<T>(deadline: &Option<Self>, receiver: &mut mpsc::Receiver<T>) -> Option<T> {
+        let timeout = async {
+            match deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline.instant).await,
+                None => future::pending().await,
+            }
+        };
+        tokio::select! {
+            message = receiver.recv() => message,
+            _ = timeout => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn messages_before_the_deadline_are_received() {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let deadline = Deadline::from_seconds(Some(60));
+        sender.send(1).await.unwrap();
+        assert_eq!(Deadline::recv(&deadline, &mut receiver).await, Some(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn receiving_stops_at_the_deadline() {
+        let (_sender, mut receiver) = mpsc::channel::<()>(1);
+        let start = Instant::now();
+        let deadline = Deadline::from_seconds(Some(60));
+        assert_eq!(Deadline::recv(&deadline, &mut receiver).await, None);
+        assert_eq!(start.elapsed(), Duration::from_secs(60));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn phases_without_a_deadline_never_time_out() {
+        let (_sender, mut receiver) = mpsc::channel::<()>(1);
+        let message = tokio::time::timeout(
+            Duration::from_secs(24 * 60 * 60),
+            Deadline::recv(&None, &mut receiver),
+        )
+        .await;
+        assert!(message.is_err());
+    }
+}
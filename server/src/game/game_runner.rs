@@ -1,4 +1,4 @@
-use std::{cmp::Ordering, collections::HashMap};
+use std::{cmp::Ordering, collections::HashMap, mem};
 
 use anyhow::Error;
 use futures::{stream::FuturesUnordered, Future, StreamExt};
@@ -8,13 +8,21 @@ use tokio::{
     sync::{broadcast, mpsc, oneshot},
 };
 use werewolf_rs::{
-    game::{CauseOfDeath, Role, RoleData},
-    packet::{InteractionFollowup, InteractionRequest, InteractionResponse},
+    game::{CauseOfDeath, Faction, GameConfig, PhaseDurations, Role, RoleData, TieBreakRule},
+    packet::{
+        Announcement, InteractionFollowup, InteractionRequest, InteractionResponse, PacketToClient,
+        TargetPurpose, VotePurpose,
+    },
     util::{InteractionId, PlayerId},
 };
 
 use super::{
-    client_manager::ClientEvent, roles::ServerRoleDelegator, GameConfig, GameLobby, GameLobbyEvent,
+    client_manager::{ClientEvent, InteractionEvent},
+    deadline::Deadline,
+    interaction::select_target,
+    roles::ServerRoleDelegator,
+    win_condition::check_winner,
+    GameData, GameLobby, GameLobbyEvent,
 };
 
 /*
@@ -22,6 +30,8 @@ A struct that handles the game logic.
 This does not contain the game state, as that is handled by the GameLobby
 */
 pub struct GameRunner {
+    //The number of this game in its lobby
+    game_number: u32,
     game_config: GameConfig,
     lobby_sender: mpsc::Sender<GameLobbyEvent>,
     game_cancel: broadcast::Sender<()>, //This is mainly intented to create new receivers for the channel
@@ -32,11 +42,13 @@ impl GameRunner {
     Creates a new game runner and a broadcast sender that can be used to cancel the game (by stopping all created tasks at their next .await)
     */
     pub fn new(
+        game_number: u32,
         game_config: GameConfig,
         lobby_sender: mpsc::Sender<GameLobbyEvent>,
         game_cancel: broadcast::Sender<()>,
     ) -> Self {
         GameRunner {
+            game_number,
             game_config,
             lobby_sender,
             game_cancel,
@@ -53,6 +65,13 @@ impl GameRunner {
                     if let Err(e) = res {
                         error!("Error running game: {:?}", e);
                     }
+                    //Let the lobby know that it may start another game
+                    let finished = GameLobbyEvent::GameFinished {
+                        game_number: self.game_number,
+                    };
+                    if self.lobby_sender.send(finished).await.is_err() {
+                        error!("Error notifying the game lobby that the game has finished");
+                    }
                 }
             };
         });
@@ -61,10 +80,40 @@ impl GameRunner {
     async fn run(&mut self) -> Result<(), Error> {
         self.assign_roles().await?;
 
-        //The main game loop
+        //The main game loop, which runs until one of the factions has won.
+        //There is no winner before the first night, as the thief may still take an undealt werewolf card
         loop {
             self.run_night().await?;
+            if self.check_game_over().await? {
+                break;
+            }
             self.run_day().await?;
+            if self.check_game_over().await? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /*
+    Checks whether a faction has won and ends the game if that is the case.
+    Returns whether the game is over
+    */
+    async fn check_game_over(&mut self) -> Result<bool, Error> {
+        let winner =
+            GameLobby::access_game_data(&self.lobby_sender, |game_data, _| check_winner(game_data))
+                .await?;
+        match winner {
+            Some((winner, winning_players)) => {
+                self.lobby_sender
+                    .send(GameLobbyEvent::GameOver {
+                        winner,
+                        winning_players,
+                    })
+                    .await?;
+                Ok(true)
+            }
+            None => Ok(false),
         }
     }
 
@@ -88,14 +137,25 @@ impl GameRunner {
                 remaining_roles.swap_remove(idx);
             }
         }
+        //The thief can choose between two undealt cards, which are villagers if there are not enough cards
+        if self.game_config.roles.contains(&Role::Thief) {
+            while remaining_roles.len() < 2 {
+                remaining_roles.push(Role::Villager);
+            }
+        }
         GameLobby::access_game_data(&self.lobby_sender, move |game_data, _| {
+            game_data.undealt_cards = remaining_roles;
+            //The players sit in a random order, which stays the same for the whole game
+            game_data.seating = client_ids.clone();
             for (client_id, assigned_role) in client_ids.iter().zip(client_roles) {
                 match game_data.players.get_mut(client_id) {
                     None => {
                         warn!("A client seems to have disconnected during role assignment");
                     }
                     Some(player) => {
+                        player.faction = assigned_role.get_role().faction();
                         player.role_data = assigned_role;
+                        player.is_alive = true;
                     }
                 }
             }
@@ -106,105 +166,380 @@ impl GameRunner {
     }
 
     async fn run_night(&mut self) -> Result<(), Error> {
+        GameLobby::access_game_data(&self.lobby_sender, |game_data, _| game_data.night += 1)
+            .await?;
         //A list of all the involved roles and whether they have already been run in this night
-        let unique_roles = GameLobby::access_game_data(&self.lobby_sender, |game_data, _| {
-            let mut unique_roles: Vec<(Role, bool)> = Vec::new();
-            for player in game_data.players.values().filter(|p| p.is_alive) {
-                if !unique_roles.contains(&(player.role_data.get_role(), false)) {
-                    unique_roles.push((player.role_data.get_role(), false));
+        let mut unique_roles: Vec<(Role, bool)> = self
+            .night_turns()
+            .await?
+            .into_iter()
+            .map(|turn| (turn, false))
+            .collect();
+        //Send the role on this channel whenever it has finished running
+        let (role_finish_send, mut role_finish_rec) = mpsc::channel::<Role>(1);
+        let mut finished_roles: Vec<Role> = Vec::new();
+        loop {
+            for role in Self::startable_roles(&unique_roles, &finished_roles) {
+                if let Some((_, has_run)) = unique_roles.iter_mut().find(|(r, _)| *r == role) {
+                    *has_run = true;
                 }
+                let finish_send = role_finish_send.clone();
+                let lobby_sender = self.lobby_sender.clone();
+                let game_config = self.game_config.clone();
+                self.spawn_task(async move {
+                    let res = role.run_night_turn(&lobby_sender, &game_config).await;
+                    //The night goes on even if the turn of a role has failed
+                    finish_send.send(role).await?;
+                    res
+                })
+                .await;
+            }
+            //The night is over once no role is running anymore
+            let started_roles = unique_roles.iter().filter(|(_, has_run)| *has_run).count();
+            if finished_roles.len() == started_roles {
+                break;
             }
-            unique_roles
+            match role_finish_rec.recv().await {
+                Some(role) => {
+                    //The card the thief has taken may add turns to this night
+                    if role == Role::Thief {
+                        for turn in self.night_turns().await? {
+                            if !unique_roles.iter().any(|(r, _)| *r == turn) {
+                                unique_roles.push((turn, false));
+                            }
+                        }
+                    }
+                    finished_roles.push(role);
+                }
+                None => break,
+            }
+        }
+
+        //Apply the changes that happened during the night (but only take effect now)
+        GameLobby::access_game_data(&self.lobby_sender, |game_data, _| {
+            Self::resolve_protections(game_data)
         })
         .await?;
-        //Send a message on this channel whenever a role has finished running
-        let (role_finish_send, mut role_finish_rec) = mpsc::channel::<()>(1);
-        role_finish_send.send(()).await?;
-        while let Some(()) = role_finish_rec.recv().await {
-            let mut finished = true;
-            let unfinished_roles = unique_roles
-                .iter()
-                .filter(|(_, has_run)| !has_run)
-                .map(|(role, _)| role);
-            for role in unfinished_roles.clone() {
-                finished = false;
-                //Check whether all dependencies have been fulfilled
-                //This could be done a lot more efficiently but it's unlikely to ever be performance-critical
-                let mut deps_fulfilled = true;
-                'dep_check: for unfinished_role in unfinished_roles.clone() {
-                    for dependency in role.dependencies_in_night() {
-                        if dependency == *unfinished_role {
-                            deps_fulfilled = false;
-                            break 'dep_check;
-                        }
+        self.apply_deaths().await?;
+        self.announce_morning().await
+    }
+
+    /*
+    The turns that take place in the current night, given the current roles of the living players
+    */
+    async fn night_turns(&self) -> Result<Vec<Role>, Error> {
+        let game_config = self.game_config.clone();
+        GameLobby::access_game_data(&self.lobby_sender, move |game_data, _| {
+            let mut night_turns: Vec<Role> = Vec::new();
+            for player in game_data.players.values().filter(|p| p.is_alive) {
+                let role = player.role_data.get_role();
+                let mut turns = vec![role.clone()];
+                //The vote of the pack takes place as long as any of its members is alive
+                if player.faction == Some(Faction::Werewolves) {
+                    turns.push(Role::Werewolf);
+                }
+                for turn in turns {
+                    if game_data.has_powers(&turn)
+                        && turn.wakes_up_in_night(game_data.night, &game_config)
+                        && !night_turns.contains(&turn)
+                    {
+                        night_turns.push(turn);
                     }
                 }
+            }
+            night_turns
+        })
+        .await
+    }
+
+    /*
+    Lets the roles of the living players make their public announcements for the new day
+    */
+    async fn announce_morning(&mut self) -> Result<(), Error> {
+        let announcements: Vec<Announcement> =
+            GameLobby::access_game_data(&self.lobby_sender, |game_data, _| {
+                game_data
+                    .players
+                    .iter()
+                    .filter(|(_, player)| player.is_alive)
+                    .filter(|(_, player)| game_data.has_powers(&player.role_data.get_role()))
+                    .filter_map(|(id, player)| {
+                        player
+                            .role_data
+                            .get_role()
+                            .morning_announcement(game_data, *id)
+                    })
+                    .collect()
+            })
+            .await?;
+        for announcement in announcements {
+            self.lobby_sender
+                .send(GameLobbyEvent::Broadcast(
+                    PacketToClient::MorningAnnouncement(announcement),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
 
-                if deps_fulfilled {
-                    let finish_send = role_finish_send.clone();
-                    let role = role.clone();
-                    let lobby_sender = self.lobby_sender.clone();
-                    self.spawn_task(async move {
-                        role.run_night_turn(&lobby_sender).await?;
-                        finish_send.send(()).await?;
-                        Ok(())
+    /*
+    Cancels the deaths that the protections of this night prevent
+    */
+    fn resolve_protections(game_data: &mut GameData) {
+        let protected_players = mem::take(&mut game_data.protected_players);
+        game_data.dying_players.retain(|(id, cause)| {
+            !(protected_players.contains(id) && matches!(cause, CauseOfDeath::Werewolves))
+        });
+    }
+
+    /*
+    Kills all dying players and lets their roles react to their death.
+    Deaths caused by these reactions are applied as well, until no one is dying anymore
+    */
+    async fn apply_deaths(&mut self) -> Result<(), Error> {
+        loop {
+            let dying_players = GameLobby::access_game_data(&self.lobby_sender, |game_data, _| {
+                mem::take(&mut game_data.dying_players)
+            })
+            .await?;
+            if dying_players.is_empty() {
+                return Ok(());
+            }
+            for (player_id, cause) in dying_players {
+                let (died_send, died_receive) = oneshot::channel();
+                self.lobby_sender
+                    .send(GameLobbyEvent::KillPlayer(player_id, cause, died_send))
+                    .await?;
+                //The player may have already died or survived the attempt
+                let role = match died_receive.await? {
+                    Some(role) => role,
+                    None => continue,
+                };
+                let has_powers =
+                    GameLobby::access_game_data(&self.lobby_sender, move |game_data, _| {
+                        game_data.has_powers(&role).then_some(role)
                     })
-                    .await;
+                    .await?;
+                if let Some(role) = has_powers {
+                    role.on_death(&self.lobby_sender, &self.game_config, player_id)
+                        .await?;
                 }
+                self.name_successor(player_id).await?;
             }
-            if finished {
-                break;
+        }
+    }
+
+    /*
+    Returns the roles that haven't run in this night yet, but whose dependencies have all finished.
+    Dependencies that don't have a turn in this night are ignored
+    */
+    fn startable_roles(unique_roles: &[(Role, bool)], finished_roles: &[Role]) -> Vec<Role> {
+        unique_roles
+            .iter()
+            .filter(|(_, has_run)| !has_run)
+            .filter(|(role, _)| {
+                role.dependencies_in_night().iter().all(|dependency| {
+                    finished_roles.contains(dependency)
+                        || !unique_roles.iter().any(|(other, _)| other == dependency)
+                })
+            })
+            .map(|(role, _)| role.clone())
+            .collect()
+    }
+
+    async fn run_day(&mut self) -> Result<(), Error> {
+        let first_day =
+            GameLobby::access_game_data(&self.lobby_sender, |game_data, _| game_data.night == 1)
+                .await?;
+        if first_day && self.game_config.elect_mayor {
+            self.elect_mayor().await?;
+        }
+
+        let village_vote = Self::nomination_vote(
+            &self.lobby_sender,
+            &self.game_config.durations,
+            VotePurpose::Lynch,
+            None,
+        )
+        .await?;
+        //The vote of the mayor counts double and decides between tied players
+        let (vote_weights, mayor) =
+            GameLobby::access_game_data(&self.lobby_sender, |game_data, _| {
+                let mayor = game_data
+                    .players
+                    .iter()
+                    .find(|(_, player)| player.is_alive && player.status.is_mayor)
+                    .map(|(id, _)| *id);
+                let vote_weights: HashMap<PlayerId, u32> =
+                    mayor.into_iter().map(|mayor| (mayor, 2)).collect();
+                (vote_weights, mayor)
+            })
+            .await?;
+        let tally = |votes: &[(PlayerId, PlayerId)]| {
+            let most_voted = Self::most_voted(votes, &vote_weights);
+            let mayor_vote = votes
+                .iter()
+                .find(|(voter, _)| Some(*voter) == mayor)
+                .map(|(_, vote)| *vote);
+            match mayor_vote {
+                Some(mayor_vote) if most_voted.len() > 1 && most_voted.contains(&mayor_vote) => {
+                    vec![mayor_vote]
+                }
+                _ => most_voted,
             }
+        };
+
+        let mut lynched_players = tally(&village_vote);
+        let mut tie_break_rule = None;
+        if lynched_players.len() > 1 {
+            //The vote is ambiguous, so the configured rule decides who dies
+            tie_break_rule = Some(self.game_config.tie_break_rule.clone());
+            lynched_players = match self.game_config.tie_break_rule {
+                TieBreakRule::Runoff => {
+                    let runoff_vote = Self::nomination_vote(
+                        &self.lobby_sender,
+                        &self.game_config.durations,
+                        VotePurpose::Runoff,
+                        Some(lynched_players),
+                    )
+                    .await?;
+                    let mut runoff_result = tally(&runoff_vote);
+                    //There is no second runoff if the players are tied again
+                    if runoff_result.len() > 1 {
+                        runoff_result.clear();
+                    }
+                    runoff_result
+                }
+                TieBreakRule::NoLynch => Vec::new(),
+                TieBreakRule::KillAll => lynched_players,
+                TieBreakRule::Random => lynched_players
+                    .choose(&mut rand::thread_rng())
+                    .copied()
+                    .into_iter()
+                    .collect(),
+            };
         }
 
-        //Apply the changes that happened during the night (but only take effect now)
-        self.lobby_sender.send(GameLobbyEvent::ApplyDeaths).await?;
+        self.lobby_sender
+            .send(GameLobbyEvent::Broadcast(
+                PacketToClient::VillageVoteResult {
+                    lynched_players: lynched_players.clone(),
+                    tie_break_rule,
+                },
+            ))
+            .await?;
+        GameLobby::access_game_data(&self.lobby_sender, move |game_data, _| {
+            for player in lynched_players {
+                game_data
+                    .dying_players
+                    .push((player, CauseOfDeath::VillageVote));
+            }
+        })
+        .await?;
+        self.apply_deaths().await
+    }
+
+    /*
+    Lets the village elect a mayor. If the vote is tied, one of the tied players is chosen at random
+    */
+    async fn elect_mayor(&mut self) -> Result<(), Error> {
+        let election = Self::nomination_vote(
+            &self.lobby_sender,
+            &self.game_config.durations,
+            VotePurpose::MayorElection,
+            None,
+        )
+        .await?;
+        let mayor = Self::most_voted(&election, &HashMap::new())
+            .choose(&mut rand::thread_rng())
+            .copied();
+        if let Some(mayor) = mayor {
+            GameLobby::access_game_data(&self.lobby_sender, move |game_data, _| {
+                if let Some(player) = game_data.players.get_mut(&mayor) {
+                    player.status.is_mayor = true;
+                }
+            })
+            .await?;
+            self.lobby_sender.send(GameLobbyEvent::SendUpdate).await?;
+        }
         Ok(())
     }
 
-    async fn run_day(&mut self) -> Result<(), Error> {
-        //TODO Add some more information to the nomination_vote function to indicate what is being voted on
-        let village_vote = Self::nomination_vote(&self.lobby_sender).await?;
+    /*
+    If the player that has died was the mayor, they may name one of the living players as their successor.
+    Otherwise the village has no mayor anymore
+    */
+    async fn name_successor(&mut self, player_id: PlayerId) -> Result<(), Error> {
+        let selectable = GameLobby::access_game_data(&self.lobby_sender, move |game_data, _| {
+            let player = game_data.players.get_mut(&player_id)?;
+            if !player.status.is_mayor {
+                return None;
+            }
+            player.status.is_mayor = false;
+            Some(
+                game_data
+                    .players
+                    .iter()
+                    .filter(|(_, player)| player.is_alive)
+                    .map(|(id, _)| *id)
+                    .collect::<Vec<PlayerId>>(),
+            )
+        })
+        .await?;
+        let selectable = match selectable {
+            Some(selectable) if !selectable.is_empty() => selectable,
+            _ => return Ok(()),
+        };
+
+        let (interaction, successor) = select_target(
+            &self.lobby_sender,
+            player_id,
+            TargetPurpose::MayorSuccessor,
+            selectable,
+            Deadline::from_seconds(self.game_config.durations.night_action),
+        )
+        .await?;
+        interaction.close().await?;
+        if let Some(successor) = successor {
+            GameLobby::access_game_data(&self.lobby_sender, move |game_data, _| {
+                if let Some(player) = game_data.players.get_mut(&successor) {
+                    player.status.is_mayor = true;
+                }
+            })
+            .await?;
+        }
+        self.lobby_sender.send(GameLobbyEvent::SendUpdate).await?;
+        Ok(())
+    }
+
+    /*
+    Counts (voter, vote) tuples and returns the players with the most votes.
+    The vote of a player counts as often as their weight, which is 1 for players without an entry
+    */
+    pub fn most_voted(
+        votes: &[(PlayerId, PlayerId)],
+        vote_weights: &HashMap<PlayerId, u32>,
+    ) -> Vec<PlayerId> {
         let mut voted_for: HashMap<PlayerId, u32> = HashMap::new();
-        for (_, vote) in village_vote {
-            voted_for
-                .entry(vote)
-                .and_modify(|count| *count += 1)
-                .or_insert(1);
+        for (voter, vote) in votes {
+            let weight = vote_weights.get(voter).copied().unwrap_or(1);
+            *voted_for.entry(*vote).or_insert(0) += weight;
         }
-        /*
-        TODO Add different options for how to handle ambiguous voting results, such as:
-        * Rerun the vote with only the most voted for players as an option
-        * Kill no one this round
-        * Kill all players that have been selected
-        */
         let mut max_found_votes = 0;
-        let mut vote_unambiguous = true;
-        let mut voted_player = None;
+        let mut voted_players = Vec::new();
         for (player, &count) in voted_for.iter() {
             match count.cmp(&max_found_votes) {
                 Ordering::Greater => {
                     max_found_votes = count;
-                    vote_unambiguous = true;
-                    voted_player = Some(player);
+                    voted_players = vec![*player];
                 }
                 Ordering::Equal => {
-                    vote_unambiguous = false;
+                    voted_players.push(*player);
                 }
                 Ordering::Less => {}
             }
         }
-        if let Some(voted_player) = voted_player {
-            if vote_unambiguous {
-                self.lobby_sender
-                    .send(GameLobbyEvent::KillPlayer(
-                        *voted_player,
-                        CauseOfDeath::VillageVote,
-                    ))
-                    .await?;
-            }
-        }
-        Ok(())
+        voted_players
     }
 
     //Spawn a new task that stops when the game is cancelled
@@ -228,41 +563,76 @@ impl GameRunner {
     }
 
     /*
-    Runs a nomination vote where all alive players can vote and be nominated
+    Runs a nomination vote where all alive players can vote and be nominated.
+    If a list of candidates is given, the nomination phase is skipped and only the candidates can be voted for.
+    Players that are disconnected abstain until they reconnect, as do players that haven't responded in time.
     Returns the result as a vector of (player, vote) tuples
     */
     async fn nomination_vote(
         lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+        durations: &PhaseDurations,
+        purpose: VotePurpose,
+        candidates: Option<Vec<PlayerId>>,
     ) -> Result<Vec<(PlayerId, PlayerId)>, Error> {
         enum VotingStatus {
             NotVoting,
+            //The player is disconnected and does not take part in the current phase of the vote
+            Absent,
             NominationPending,
             NominationFinished(Option<PlayerId>),
             VoteFinished(PlayerId),
         }
+        type ClientMap = HashMap<PlayerId, (mpsc::Sender<ClientEvent>, VotingStatus)>;
 
         //Mapping from client id to (client_sender, voting_status)
-        let mut clients: HashMap<PlayerId, (mpsc::Sender<ClientEvent>, VotingStatus)> =
+        let mut clients: ClientMap =
             GameLobby::access_game_data(lobby_sender, |game_data, clients| {
                 let mut ret_clients = HashMap::new();
                 for (player_id, player) in game_data.players.iter() {
-                    ret_clients.insert(
-                        *player_id,
-                        (
-                            clients.get(player_id).unwrap().clone(),
-                            if player.is_alive {
-                                VotingStatus::NominationPending
-                            } else {
-                                VotingStatus::NotVoting
-                            },
-                        ),
-                    );
+                    match clients.get(player_id) {
+                        None => {
+                            warn!("Player without a client manager during nomination vote");
+                        }
+                        Some(sender) => {
+                            ret_clients.insert(
+                                *player_id,
+                                (
+                                    sender.clone(),
+                                    if player.is_alive && player.can_vote() {
+                                        VotingStatus::NominationPending
+                                    } else {
+                                        VotingStatus::NotVoting
+                                    },
+                                ),
+                            );
+                        }
+                    }
                 }
                 ret_clients
             })
             .await?;
 
+        let nominatable_players: Vec<PlayerId> = match &candidates {
+            Some(candidates) => candidates.clone(),
+            None => clients
+                .iter()
+                .filter(|(_, (_, voting_status))| !matches!(voting_status, VotingStatus::NotVoting))
+                .map(|(id, _)| *id)
+                .collect(),
+        };
+        if candidates.is_some() {
+            for (_, voting_status) in clients.values_mut() {
+                if let VotingStatus::NominationPending = voting_status {
+                    *voting_status = VotingStatus::NominationFinished(None);
+                }
+            }
+        }
+
         //Create the interactions and collect the interaction ID for each client in a hashmap
+        let nomination_deadline = match candidates {
+            Some(_) => None,
+            None => Deadline::from_seconds(durations.nomination),
+        };
         let mut id_futs = FuturesUnordered::new();
         let (interaction_send, mut interaction_receive) = mpsc::channel(8);
         for (&id, (sender, voting_status)) in clients.iter() {
@@ -270,14 +640,10 @@ impl GameRunner {
             sender
                 .send(ClientEvent::CreateInteraction(
                     InteractionRequest::NvBegin {
-                        nominatable_players: clients
-                            .iter()
-                            .filter(|(_, (_, voting_status))| {
-                                !matches!(voting_status, VotingStatus::NotVoting)
-                            })
-                            .map(|(id, _)| *id)
-                            .collect(),
+                        purpose: purpose.clone(),
+                        nominatable_players: nominatable_players.clone(),
                         can_vote: !matches!(voting_status, &VotingStatus::NotVoting),
+                        deadline: Deadline::timestamp(&nomination_deadline),
                     },
                     interaction_send.clone(),
                     id_send,
@@ -291,79 +657,191 @@ impl GameRunner {
             interaction_ids.insert(user_id, interaction_id);
         }
 
+        async fn send_followup(
+            followup: InteractionFollowup,
+            clients: &ClientMap,
+            interaction_ids: &HashMap<PlayerId, InteractionId>,
+        ) -> Result<(), Error> {
+            for (client_id, (sender, _)) in clients.iter() {
+                sender
+                    .send(ClientEvent::FollowupInteraction(
+                        interaction_ids[client_id],
+                        followup.clone(),
+                    ))
+                    .await?;
+            }
+            Ok(())
+        }
+
+        /*
+        Updates the status of a player whose connection has changed and notifies all clients.
+        A reconnecting player takes part in the current phase of the vote again
+        */
+        async fn handle_connection_change(
+            client_id: PlayerId,
+            event: &InteractionEvent,
+            reconnected_status: VotingStatus,
+            clients: &mut ClientMap,
+            interaction_ids: &HashMap<PlayerId, InteractionId>,
+        ) -> Result<(), Error> {
+            let voting_status = match clients.get_mut(&client_id) {
+                Some((_, voting_status)) => voting_status,
+                None => return Ok(()),
+            };
+            let followup = match (event, &voting_status) {
+                (InteractionEvent::Disconnected, VotingStatus::NominationPending)
+                | (InteractionEvent::Disconnected, VotingStatus::NominationFinished(_)) => {
+                    *voting_status = VotingStatus::Absent;
+                    InteractionFollowup::PlayerAbsent { player: client_id }
+                }
+                (InteractionEvent::Reconnected, VotingStatus::Absent) => {
+                    *voting_status = reconnected_status;
+                    InteractionFollowup::PlayerReturned { player: client_id }
+                }
+                _ => return Ok(()),
+            };
+            send_followup(followup, clients, interaction_ids).await
+        }
+
         //Accept all nominations
-        while let Some((client_id, response)) = interaction_receive.recv().await {
-            match response {
-                InteractionResponse::NvNominate { nominated_player } => {
-                    let (_, voting_status) = clients.get_mut(&client_id).unwrap();
-                    if let VotingStatus::NominationPending = voting_status {
-                        *voting_status = VotingStatus::NominationFinished(nominated_player);
-                        //Notify all other clients of the nomination
-                        for (other_client, (sender, _)) in clients.iter() {
-                            let interaction_id = interaction_ids[other_client];
-                            sender
-                                .send(ClientEvent::FollowupInteraction(
-                                    interaction_id,
-                                    InteractionFollowup::NvNewNomination {
-                                        nominated_player,
-                                        nominated_by: client_id,
-                                    },
-                                ))
-                                .await?;
+        while clients
+            .values()
+            .any(|(_, voting_status)| matches!(voting_status, VotingStatus::NominationPending))
+        {
+            let (client_id, event) =
+                match Deadline::recv(&nomination_deadline, &mut interaction_receive).await {
+                    Some(event) => event,
+                    None => break,
+                };
+            match event {
+                InteractionEvent::Response(InteractionResponse::NvNominate {
+                    nominated_player,
+                }) => {
+                    match clients.get_mut(&client_id) {
+                        Some((_, voting_status @ VotingStatus::NominationPending)) => {
+                            if let Some(nominated_player) = nominated_player {
+                                if !nominatable_players.contains(&nominated_player) {
+                                    warn!(
+                                        "Received nomination of a player that can't be nominated"
+                                    );
+                                    continue;
+                                }
+                            }
+                            *voting_status = VotingStatus::NominationFinished(nominated_player);
+                            //Notify all other clients of the nomination
+                            send_followup(
+                                InteractionFollowup::NvNewNomination {
+                                    nominated_player,
+                                    nominated_by: client_id,
+                                },
+                                &clients,
+                                &interaction_ids,
+                            )
+                            .await?;
                         }
-
-                        //Break out of the loop once all pending nominations have been received
-                        if clients.values().all(|(_, voting_status)| {
-                            !matches!(voting_status, VotingStatus::NominationPending)
-                        }) {
-                            break;
+                        _ => {
+                            warn!("Received Nomination from client that is not currently allowed to nominate");
                         }
-                    } else {
-                        warn!("Received Nomination from client that is not currently allowed to nominate");
                     }
                 }
-                r => {
+                InteractionEvent::Response(r) => {
                     warn!(
                         "Received invalid interaction response during nomination phase: {:?}",
                         r
                     );
                 }
+                event => {
+                    handle_connection_change(
+                        client_id,
+                        &event,
+                        VotingStatus::NominationPending,
+                        &mut clients,
+                        &interaction_ids,
+                    )
+                    .await?;
+                }
             }
         }
-        for (client_id, (sender, _)) in clients.iter() {
-            let interaction_id = interaction_ids[client_id];
-            sender
-                .send(ClientEvent::FollowupInteraction(
-                    interaction_id,
-                    InteractionFollowup::NvNominationsFinished,
-                ))
-                .await?;
+        //Players that haven't nominated anyone in time can still vote
+        for (_, voting_status) in clients.values_mut() {
+            if let VotingStatus::NominationPending = voting_status {
+                *voting_status = VotingStatus::NominationFinished(None);
+            }
+        }
+        let nominated_players: Vec<PlayerId> = match candidates {
+            Some(candidates) => candidates,
+            None => clients
+                .values()
+                .filter_map(|(_, voting_status)| match voting_status {
+                    VotingStatus::NominationFinished(nominated_player) => *nominated_player,
+                    _ => None,
+                })
+                .collect(),
+        };
+        //Without any nominations there is no one to vote for, so the voting phase is skipped
+        if nominated_players.is_empty() {
+            for (_, voting_status) in clients.values_mut() {
+                if let VotingStatus::NominationFinished(_) = voting_status {
+                    *voting_status = VotingStatus::NotVoting;
+                }
+            }
         }
+        let vote_deadline = match nominated_players[..] {
+            [] => None,
+            _ => Deadline::from_seconds(durations.village_vote),
+        };
+        send_followup(
+            InteractionFollowup::NvNominationsFinished {
+                deadline: Deadline::timestamp(&vote_deadline),
+            },
+            &clients,
+            &interaction_ids,
+        )
+        .await?;
 
         //Accept votes
-        while let Some((client_id, response)) = interaction_receive.recv().await {
-            match response {
-                InteractionResponse::NvVote { player: player_id } => {
-                    let (_, voting_status) = clients.get_mut(&client_id).unwrap();
-                    if let VotingStatus::NominationFinished(_) = voting_status {
-                        *voting_status = VotingStatus::VoteFinished(player_id);
-
-                        //Break out of the loop once all pending votes have been received
-                        if clients.values().all(|(_, voting_status)| {
-                            !matches!(voting_status, VotingStatus::NominationFinished(_))
-                        }) {
-                            break;
+        while clients
+            .values()
+            .any(|(_, voting_status)| matches!(voting_status, VotingStatus::NominationFinished(_)))
+        {
+            let (client_id, event) =
+                match Deadline::recv(&vote_deadline, &mut interaction_receive).await {
+                    Some(event) => event,
+                    None => break,
+                };
+            match event {
+                InteractionEvent::Response(InteractionResponse::NvVote { player: player_id }) => {
+                    match clients.get_mut(&client_id) {
+                        Some((_, voting_status @ VotingStatus::NominationFinished(_))) => {
+                            if nominated_players.contains(&player_id) {
+                                *voting_status = VotingStatus::VoteFinished(player_id);
+                            } else {
+                                warn!("Received vote for a player that hasn't been nominated");
+                            }
+                        }
+                        _ => {
+                            warn!(
+                                "Received vote from client that is not currently allowed to vote"
+                            );
                         }
-                    } else {
-                        warn!("Received vote from client that is not currently allowed to vote");
                     }
                 }
-                r => {
+                InteractionEvent::Response(r) => {
                     warn!(
                         "Received invalid interaction response during voting phase: {:?}",
                         r
                     );
                 }
+                event => {
+                    handle_connection_change(
+                        client_id,
+                        &event,
+                        VotingStatus::NominationFinished(None),
+                        &mut clients,
+                        &interaction_ids,
+                    )
+                    .await?;
+                }
             }
         }
 
@@ -375,20 +853,332 @@ impl GameRunner {
                 _ => None,
             })
             .collect();
+        send_followup(
+            InteractionFollowup::NvVoteFinished {
+                votes: vote_result.clone(),
+            },
+            &clients,
+            &interaction_ids,
+        )
+        .await?;
         for (client, (sender, _)) in clients.iter() {
-            let interaction_id = interaction_ids[client];
             sender
-                .send(ClientEvent::FollowupInteraction(
-                    interaction_id,
-                    InteractionFollowup::NvVoteFinished {
-                        votes: vote_result.clone(),
-                    },
-                ))
-                .await?;
-            sender
-                .send(ClientEvent::CloseInteraction(interaction_id))
+                .send(ClientEvent::CloseInteraction(interaction_ids[client]))
                 .await?;
         }
         Ok(vote_result)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use werewolf_rs::{game::LobbyPhase, packet::TargetPurpose, util::LobbyId};
+
+    use super::*;
+    use crate::game::{GameData, Player};
+
+    /*
+    Starts a lobby with a running game in which the players have the given roles.
+    Only the given clients are connected to the lobby
+    */
+    fn start_game(
+        roles: &[Role],
+        clients: HashMap<PlayerId, mpsc::Sender<ClientEvent>>,
+    ) -> GameRunner {
+        start_lobby(GameData::with_roles(roles), clients, GameConfig::default())
+    }
+
+    /*
+    Starts a lobby with a running game with the given game data, and creates the runner for that game
+    */
+    fn start_lobby(
+        game_data: GameData,
+        clients: HashMap<PlayerId, mpsc::Sender<ClientEvent>>,
+        game_config: GameConfig,
+    ) -> GameRunner {
+        let (lobby_manager_sender, _) = mpsc::channel(1);
+        let (mut lobby, lobby_sender) = GameLobby::new(LobbyId::new(0), lobby_manager_sender);
+        lobby.game_data = game_data;
+        lobby.clients = clients;
+        lobby.phase = LobbyPhase::InGame;
+        lobby.game_number = 1;
+        tokio::spawn(async move { lobby.run().await });
+        let (game_cancel, _) = broadcast::channel(1);
+        GameRunner::new(1, game_config, lobby_sender, game_cancel)
+    }
+
+    /*
+    A client that nominates and votes for the given player during a nomination vote, or never responds if no player is given
+    */
+    fn voting_client(player_id: PlayerId, vote: Option<PlayerId>) -> mpsc::Sender<ClientEvent> {
+        let (sender, mut receiver) = mpsc::channel(8);
+        tokio::spawn(async move {
+            let mut response_channel = None;
+            while let Some(event) = receiver.recv().await {
+                let response = match event {
+                    ClientEvent::CreateInteraction(_, channel, id_send) => {
+                        id_send.send(InteractionId::new(0)).ok();
+                        response_channel = Some(channel);
+                        InteractionResponse::NvNominate {
+                            nominated_player: vote,
+                        }
+                    }
+                    ClientEvent::FollowupInteraction(
+                        _,
+                        InteractionFollowup::NvNominationsFinished { .. },
+                    ) => match vote {
+                        Some(player) => InteractionResponse::NvVote { player },
+                        None => continue,
+                    },
+                    _ => continue,
+                };
+                if let (Some(channel), Some(_)) = (&response_channel, vote) {
+                    let event = (player_id, InteractionEvent::Response(response));
+                    channel.send(event).await.ok();
+                }
+            }
+        });
+        sender
+    }
+
+    /*
+    A client that answers every interaction request with the given responses
+    */
+    fn scripted_client<F>(player_id: PlayerId, respond: F) -> mpsc::Sender<ClientEvent>
+    where
+        F: Fn(&InteractionRequest) -> Vec<InteractionResponse> + Send + 'static,
+    {
+        let (sender, mut receiver) = mpsc::channel(8);
+        tokio::spawn(async move {
+            while let Some(event) = receiver.recv().await {
+                if let ClientEvent::CreateInteraction(request, channel, id_send) = event {
+                    id_send.send(InteractionId::new(0)).ok();
+                    for response in respond(&request) {
+                        let event = (player_id, InteractionEvent::Response(response));
+                        channel.send(event).await.ok();
+                    }
+                }
+            }
+        });
+        sender
+    }
+
+    #[tokio::test]
+    async fn night_ends_after_all_turns() {
+        let mut runner = start_game(
+            &[Role::Villager, Role::Villager, Role::Spectator],
+            HashMap::new(),
+        );
+        tokio::time::timeout(Duration::from_secs(5), runner.run_night())
+            .await
+            .expect("The night didn't end")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn players_from_the_lobby_are_alive_after_role_assignment() {
+        let mut game_data = GameData::default();
+        for idx in 0..3 {
+            let player = Player::new(format!("Player {}", idx), idx == 0);
+            game_data.players.insert(PlayerId::new(idx), player);
+        }
+        let game_config = GameConfig {
+            roles: vec![Role::Werewolf],
+            ..GameConfig::default()
+        };
+        let mut runner = start_lobby(game_data, HashMap::new(), game_config);
+        runner.assign_roles().await.unwrap();
+        let (alive, winner) = GameLobby::access_game_data(&runner.lobby_sender, |game_data, _| {
+            let alive = game_data.players.values().filter(|p| p.is_alive).count();
+            (alive, check_winner(game_data))
+        })
+        .await
+        .unwrap();
+        assert_eq!(alive, 3);
+        assert!(winner.is_none());
+    }
+
+    #[tokio::test]
+    async fn witch_heals_and_poisons_after_the_werewolves() {
+        let ids: Vec<PlayerId> = (0..4).map(PlayerId::new).collect();
+        let (victim, poisoned) = (ids[2], ids[3]);
+        let witch = scripted_client(ids[0], move |request| match request {
+            InteractionRequest::TsBegin {
+                purpose: TargetPurpose::WitchHeal,
+                ..
+            } => vec![InteractionResponse::TsSelect {
+                target: Some(victim),
+            }],
+            InteractionRequest::TsBegin {
+                purpose: TargetPurpose::WitchPoison,
+                ..
+            } => vec![InteractionResponse::TsSelect {
+                target: Some(poisoned),
+            }],
+            _ => Vec::new(),
+        });
+        let werewolf = scripted_client(ids[1], move |request| match request {
+            InteractionRequest::WvBegin { .. } => vec![
+                InteractionResponse::WvVote { vote: victim },
+                InteractionResponse::WvLockVote,
+            ],
+            _ => Vec::new(),
+        });
+        let mut runner = start_game(
+            &[Role::Witch, Role::Werewolf, Role::Villager, Role::Villager],
+            vec![(ids[0], witch), (ids[1], werewolf)]
+                .into_iter()
+                .collect(),
+        );
+        tokio::time::timeout(Duration::from_secs(5), runner.run_night())
+            .await
+            .expect("The night didn't end")
+            .unwrap();
+
+        //The witch can only heal the victim if the werewolves have already chosen them
+        let (alive, witch_data) =
+            GameLobby::access_game_data(&runner.lobby_sender, move |game_data, _| {
+                let alive: Vec<bool> = ids
+                    .iter()
+                    .map(|id| game_data.players[id].is_alive)
+                    .collect();
+                (alive, game_data.players[&ids[0]].role_data.clone())
+            })
+            .await
+            .unwrap();
+        assert_eq!(alive, vec![true, true, true, false]);
+        assert!(matches!(
+            witch_data,
+            RoleData::Witch {
+                healing_potion_used: true,
+                poison_potion_used: true,
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn stolen_card_gets_its_turn_in_the_first_night() {
+        let ids: Vec<PlayerId> = (0..3).map(PlayerId::new).collect();
+        let (first_lover, second_lover) = (ids[1], ids[2]);
+        let thief = scripted_client(ids[0], move |request| match request {
+            InteractionRequest::CsBegin { .. } => {
+                vec![InteractionResponse::CsSelect { card: Some(0) }]
+            }
+            InteractionRequest::TsBegin {
+                purpose: TargetPurpose::CupidFirstLover,
+                ..
+            } => vec![InteractionResponse::TsSelect {
+                target: Some(first_lover),
+            }],
+            InteractionRequest::TsBegin {
+                purpose: TargetPurpose::CupidSecondLover,
+                ..
+            } => vec![InteractionResponse::TsSelect {
+                target: Some(second_lover),
+            }],
+            _ => Vec::new(),
+        });
+        let mut game_data = GameData::with_roles(&[Role::Thief, Role::Villager, Role::Villager]);
+        game_data.undealt_cards = vec![Role::Cupid, Role::Villager];
+        let mut runner = start_lobby(
+            game_data,
+            vec![(ids[0], thief)].into_iter().collect(),
+            GameConfig::default(),
+        );
+        tokio::time::timeout(Duration::from_secs(5), runner.run_night())
+            .await
+            .expect("The night didn't end")
+            .unwrap();
+
+        //The former thief plays cupid in the same night
+        let lover = GameLobby::access_game_data(&runner.lobby_sender, move |game_data, _| {
+            game_data.players[&first_lover].status.lover
+        })
+        .await
+        .unwrap();
+        assert_eq!(lover, Some(second_lover));
+    }
+
+    #[test]
+    fn witch_waits_for_the_victims_of_the_night() {
+        let unique_roles = vec![(Role::Werewolf, true), (Role::Witch, false)];
+        assert_eq!(GameRunner::startable_roles(&unique_roles, &[]), Vec::new());
+        assert_eq!(
+            GameRunner::startable_roles(&unique_roles, &[Role::Werewolf]),
+            vec![Role::Witch]
+        );
+    }
+
+    #[test]
+    fn thief_chooses_once_before_everyone_else() {
+        let mut unique_roles = vec![(Role::Thief, false), (Role::Werewolf, false)];
+        assert_eq!(
+            GameRunner::startable_roles(&unique_roles, &[]),
+            vec![Role::Thief]
+        );
+        unique_roles[0].1 = true;
+        assert_eq!(GameRunner::startable_roles(&unique_roles, &[]), Vec::new());
+        assert_eq!(
+            GameRunner::startable_roles(&unique_roles, &[Role::Thief]),
+            vec![Role::Werewolf]
+        );
+    }
+
+    #[test]
+    fn vote_of_the_mayor_counts_double() {
+        let ids: Vec<PlayerId> = (0..4).map(PlayerId::new).collect();
+        let votes = vec![(ids[0], ids[3]), (ids[1], ids[2]), (ids[2], ids[2])];
+        assert_eq!(
+            GameRunner::most_voted(&votes, &HashMap::new()),
+            vec![ids[2]]
+        );
+        let mut tied = GameRunner::most_voted(&votes, &vec![(ids[0], 2)].into_iter().collect());
+        tied.sort_by_key(|id| ids.iter().position(|other| other == id));
+        assert_eq!(tied, vec![ids[2], ids[3]]);
+        let weights = vec![(ids[0], 3)].into_iter().collect();
+        assert_eq!(GameRunner::most_voted(&votes, &weights), vec![ids[3]]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn vote_is_decided_by_the_existing_votes_at_the_deadline() {
+        let ids: Vec<PlayerId> = (0..3).map(PlayerId::new).collect();
+        let clients = vec![
+            (ids[0], voting_client(ids[0], Some(ids[1]))),
+            (ids[1], voting_client(ids[1], None)),
+            (ids[2], voting_client(ids[2], None)),
+        ];
+        let runner = start_game(
+            &[Role::Villager, Role::Villager, Role::Villager],
+            clients.into_iter().collect(),
+        );
+        let votes = GameRunner::nomination_vote(
+            &runner.lobby_sender,
+            &PhaseDurations::default(),
+            VotePurpose::Lynch,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(votes, vec![(ids[0], ids[1])]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn vote_is_skipped_without_nominations() {
+        let ids: Vec<PlayerId> = (0..2).map(PlayerId::new).collect();
+        let clients = ids.iter().map(|id| (*id, voting_client(*id, None)));
+        let runner = start_game(&[Role::Villager, Role::Villager], clients.collect());
+        let durations = PhaseDurations {
+            village_vote: None,
+            ..PhaseDurations::default()
+        };
+        let vote =
+            GameRunner::nomination_vote(&runner.lobby_sender, &durations, VotePurpose::Lynch, None);
+        let votes = tokio::time::timeout(Duration::from_secs(60 * 60), vote)
+            .await
+            .expect("The vote didn't end")
+            .unwrap();
+        assert_eq!(votes, Vec::new());
+    }
+}
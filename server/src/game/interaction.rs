@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Error};
+use tokio::sync::{mpsc, oneshot};
+use werewolf_rs::{
+    packet::{InteractionFollowup, InteractionRequest, InteractionResponse, TargetPurpose},
+    util::{InteractionId, PlayerId},
+};
+
+use super::{
+    client_manager::{ClientEvent, InteractionEvent},
+    deadline::Deadline,
+    GameLobby, GameLobbyEvent,
+};
+
+/*
+An interaction with a single client, such as the night action of a role
+*/
+pub struct SingleInteraction {
+    client_sender: mpsc::Sender<ClientEvent>,
+    interaction_id: InteractionId,
+    receiver: mpsc::Receiver<(PlayerId, InteractionEvent)>,
+}
+
+impl SingleInteraction {
+    pub async fn create(
+        lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+        player_id: PlayerId,
+        request: InteractionRequest,
+    ) -> Result<Self, Error> {
+        let client_sender = GameLobby::access_game_data(lobby_sender, move |_, clients| {
+            clients.get(&player_id).cloned()
+        })
+        .await?
+        .ok_or_else(|| anyhow!("Player {:?} has no client manager", player_id))?;
+        let (interaction_send, receiver) = mpsc::channel(8);
+        let (id_send, id_receive) = oneshot::channel();
+        client_sender
+            .send(ClientEvent::CreateInteraction(
+                request,
+                interaction_send,
+                id_send,
+            ))
+            .await?;
+        let interaction_id = id_receive.await?;
+        Ok(SingleInteraction {
+            client_sender,
+            interaction_id,
+            receiver,
+        })
+    }
+
+    /*
+    Waits for the next response of the client.
+    Returns None once the deadline has passed, or if the client is disconnected and there is no deadline to wait for
+    */
+    pub async fn receive(&mut self, deadline: &Option<Deadline>) -> Option<InteractionResponse> {
+        loop {
+            match Deadline::recv(deadline, &mut self.receiver).await? {
+                (_, InteractionEvent::Response(response)) => return Some(response),
+                (_, InteractionEvent::Disconnected) if deadline.is_none() => return None,
+                _ => {}
+            }
+        }
+    }
+
+    pub async fn followup(&self, followup: InteractionFollowup) -> Result<(), Error> {
+        self.client_sender
+            .send(ClientEvent::FollowupInteraction(
+                self.interaction_id,
+                followup,
+            ))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn close(self) -> Result<(), Error> {
+        self.client_sender
+            .send(ClientEvent::CloseInteraction(self.interaction_id))
+            .await?;
+        Ok(())
+    }
+}
+
+/*
+Lets a player choose one of the selectable players as the target of an action.
+Returns None as the target if the player chose no one or didn't respond in time.
+The interaction is left open, so that the result of the action can be sent as a followup
+*/
+pub async fn select_target(
+    lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+    player_id: PlayerId,
+    purpose: TargetPurpose,
+    selectable_players: Vec<PlayerId>,
+    deadline: Option<Deadline>,
+) -> Result<(SingleInteraction, Option<PlayerId>), Error> {
+    let mut interaction = SingleInteraction::create(
+        lobby_sender,
+        player_id,
+        InteractionRequest::TsBegin {
+            purpose,
+            selectable_players: selectable_players.clone(),
+            deadline: Deadline::timestamp(&deadline),
+        },
+    )
+    .await?;
+    while let Some(response) = interaction.receive(&deadline).await {
+        match response {
+            InteractionResponse::TsSelect {
+                target: Some(target),
+            } if !selectable_players.contains(&target) => {
+                warn!("Received selection of a player that can't be selected");
+            }
+            InteractionResponse::TsSelect { target } => return Ok((interaction, target)),
+            r => {
+                warn!(
+                    "Received invalid interaction response during target selection: {:?}",
+                    r
+                );
+            }
+        }
+    }
+    Ok((interaction, None))
+}
+
+/*
+Lets a group of players talk privately, until all of them are done or the time is up.
+Disconnected players count as done until they reconnect
+*/
+pub async fn group_chat(
+    lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+    members: Vec<PlayerId>,
+    deadline: Option<Deadline>,
+) -> Result<(), Error> {
+    enum ChatStatus {
+        Talking,
+        Absent,
+        Done,
+    }
+    type ClientMap = HashMap<PlayerId, (mpsc::Sender<ClientEvent>, InteractionId, ChatStatus)>;
+
+    async fn send_followup(
+        followup: InteractionFollowup,
+        clients: &ClientMap,
+    ) -> Result<(), Error> {
+        for (sender, interaction_id, _) in clients.values() {
+            sender
+                .send(ClientEvent::FollowupInteraction(
+                    *interaction_id,
+                    followup.clone(),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+
+    let member_clients = members.clone();
+    let senders: Vec<(PlayerId, mpsc::Sender<ClientEvent>)> =
+        GameLobby::access_game_data(lobby_sender, move |_, clients| {
+            member_clients
+                .iter()
+                .filter_map(|id| clients.get(id).map(|sender| (*id, sender.clone())))
+                .collect()
+        })
+        .await?;
+    let (interaction_send, mut interaction_receive) = mpsc::channel(8);
+    let mut clients: ClientMap = HashMap::new();
+    for (id, sender) in senders {
+        let (id_send, id_receive) = oneshot::channel();
+        sender
+            .send(ClientEvent::CreateInteraction(
+                InteractionRequest::GcBegin {
+                    members: members.clone(),
+                    deadline: Deadline::timestamp(&deadline),
+                },
+                interaction_send.clone(),
+                id_send,
+            ))
+            .await?;
+        clients.insert(id, (sender, id_receive.await?, ChatStatus::Talking));
+    }
+    drop(interaction_send);
+
+    while clients
+        .values()
+        .any(|(_, _, status)| matches!(status, ChatStatus::Talking))
+    {
+        let (player_id, event) = match Deadline::recv(&deadline, &mut interaction_receive).await {
+            Some(event) => event,
+            None => break,
+        };
+        let status = match clients.get_mut(&player_id) {
+            Some((_, _, status)) => status,
+            None => continue,
+        };
+        let followup = match (event, &status) {
+            (InteractionEvent::Response(InteractionResponse::GcMessage { message }), _) => {
+                if message.trim().is_empty() {
+                    continue;
+                }
+                InteractionFollowup::GcNewMessage {
+                    message,
+                    sent_by: player_id,
+                }
+            }
+            (InteractionEvent::Response(InteractionResponse::GcDone), ChatStatus::Talking) => {
+                *status = ChatStatus::Done;
+                InteractionFollowup::GcMemberDone { player: player_id }
+            }
+            (InteractionEvent::Disconnected, ChatStatus::Talking) => {
+                *status = ChatStatus::Absent;
+                InteractionFollowup::PlayerAbsent { player: player_id }
+            }
+            (InteractionEvent::Reconnected, ChatStatus::Absent) => {
+                *status = ChatStatus::Talking;
+                InteractionFollowup::PlayerReturned { player: player_id }
+            }
+            (InteractionEvent::Response(r), _) => {
+                warn!(
+                    "Received invalid interaction response during group chat: {:?}",
+                    r
+                );
+                continue;
+            }
+            _ => continue,
+        };
+        send_followup(followup, &clients).await?;
+    }
+
+    send_followup(InteractionFollowup::GcFinished, &clients).await?;
+    for (sender, interaction_id, _) in clients.values() {
+        sender
+            .send(ClientEvent::CloseInteraction(*interaction_id))
+            .await?;
+    }
+    Ok(())
+}
@@ -1,8 +1,15 @@
 mod client_manager;
+mod deadline;
 mod game_runner;
+mod interaction;
 mod roles;
+mod visibility;
+mod win_condition;
 
-use crate::{game::game_runner::GameRunner, util::generate_id};
+use crate::{
+    game::{game_runner::GameRunner, roles::ServerRoleDelegator},
+    util::{generate_id, generate_token, send_logging},
+};
 
 use super::{
     lobby_manager::LobbyManagerEvent,
@@ -10,11 +17,12 @@ use super::{
 };
 use anyhow::Error;
 use client_manager::{ClientEvent, ClientManager};
-use std::{collections::HashMap, fmt::Debug, mem};
+use futures::SinkExt;
+use std::{collections::HashMap, fmt::Debug};
 use tokio::sync::{broadcast, mpsc, oneshot};
 use werewolf_rs::{
-    game::{CauseOfDeath, Role, RoleData},
-    packet::PacketToClient,
+    game::{CauseOfDeath, Faction, GameConfig, LobbyPhase, Role, RoleData, RoleInfo},
+    packet::{PacketToClient, RequestError},
     util::{LobbyId, PlayerId},
 };
 
@@ -25,6 +33,13 @@ pub enum GameLobbyEvent {
     NewConnection {
         ws_read: WsReceiver,
         ws_write: WsSender,
+        name: String,
+    },
+    //A client with an existing player wants to take it over using its reconnect token
+    Reconnect {
+        ws_read: WsReceiver,
+        ws_write: WsSender,
+        token: String,
     },
     ConnectionLost {
         client_id: PlayerId,
@@ -32,31 +47,98 @@ pub enum GameLobbyEvent {
     StartGame {
         requested_by: PlayerId,
     },
-    KillPlayer(PlayerId, CauseOfDeath),
-    //Kill all dying players. This happens at the end of each night
-    ApplyDeaths,
+    CancelGame {
+        requested_by: PlayerId,
+    },
+    ReturnToLobby {
+        requested_by: PlayerId,
+    },
+    UpdateGameConfig {
+        config: GameConfig,
+        requested_by: PlayerId,
+    },
+    SetName {
+        name: String,
+        requested_by: PlayerId,
+    },
+    //The game runner has stopped, either because a faction has won or because of an error
+    GameFinished {
+        game_number: u32,
+    },
+    //Kill a player and let all clients know, unless their role prevents the death.
+    //Responds with the role of the player if they have died. Reactions to the death are handled by the game runner
+    KillPlayer(PlayerId, CauseOfDeath, oneshot::Sender<Option<Role>>),
+    //Give a player a new role (and its faction), for example when the thief takes one of the undealt cards
+    ChangeRole(PlayerId, RoleData),
+    //End the game and reveal all roles to the clients
+    GameOver {
+        winner: Faction,
+        winning_players: Vec<PlayerId>,
+    },
     //Send an update to all connected clients with the updated game data
     SendUpdate,
+    //Send a packet to all connected clients
+    Broadcast(PacketToClient),
     //Run an arbitrary (non-blocking) function on the game data
     AccessGameData(GameDataFunction),
 }
 
 #[derive(Clone)]
 pub struct Player {
+    name: String,
     role_data: RoleData,
+    //The side the player currently plays for, which isn't necessarily the one of their role
+    faction: Option<Faction>,
     is_lobby_host: bool,
     is_alive: bool,
+    is_connected: bool,
+    //What this player has learned about the roles of other players during the game
+    known_roles: HashMap<PlayerId, RoleInfo>,
+    status: PlayerStatus,
+}
+
+impl Player {
+    /*
+    A player that has just joined the lobby and doesn't take part in a game yet
+    */
+    fn new(name: String, is_lobby_host: bool) -> Self {
+        Player {
+            name,
+            role_data: RoleData::Spectator,
+            faction: None,
+            is_lobby_host,
+            is_alive: false,
+            is_connected: true,
+            known_roles: HashMap::new(),
+            status: PlayerStatus::default(),
+        }
+    }
+}
+
+/*
+The state of a player in the current game that doesn't depend on their role
+*/
+#[derive(Clone, Default)]
+pub struct PlayerStatus {
+    lover: Option<PlayerId>,
+    is_mayor: bool,
+    cause_of_death: Option<CauseOfDeath>,
 }
 
 #[derive(Clone)]
 pub struct GameData {
     players: HashMap<PlayerId, Player>,
     dying_players: Vec<(PlayerId, CauseOfDeath)>,
-}
-
-#[derive(Clone)]
-pub struct GameConfig {
-    roles: Vec<Role>,
+    //Players that can't be killed by the werewolves in the current night
+    protected_players: Vec<PlayerId>,
+    //The role cards that have not been dealt to any player
+    undealt_cards: Vec<Role>,
+    //The players of the game in the order they sit in a circle
+    seating: Vec<PlayerId>,
+    //Whether the villagers have lost their special abilities, because the village has lynched the elder
+    village_powers_lost: bool,
+    //The number of the current (or last) night, starting at 1
+    night: u32,
 }
 
 pub struct GameLobby {
@@ -67,14 +149,12 @@ pub struct GameLobby {
     game_cancel: broadcast::Sender<()>,
 
     clients: HashMap<PlayerId, mpsc::Sender<ClientEvent>>,
+    reconnect_tokens: HashMap<String, PlayerId>,
     game_data: GameData,
     game_config: GameConfig,
-}
-
-impl Default for GameConfig {
-    fn default() -> Self {
-        GameConfig { roles: Vec::new() }
-    }
+    phase: LobbyPhase,
+    //Counts the games started in this lobby, so that events of a cancelled game can be told apart
+    game_number: u32,
 }
 
 impl Default for GameData {
@@ -82,7 +162,83 @@ impl Default for GameData {
         GameData {
             players: HashMap::new(),
             dying_players: Vec::new(),
+            protected_players: Vec::new(),
+            undealt_cards: Vec::new(),
+            seating: Vec::new(),
+            village_powers_lost: false,
+            night: 0,
+        }
+    }
+}
+
+impl Player {
+    /*
+    Whether the player may vote during the day
+    */
+    fn can_vote(&self) -> bool {
+        !matches!(self.role_data, RoleData::VillageIdiot { revealed: true })
+    }
+}
+
+impl GameData {
+    /*
+    Whether players with this role can still use their special abilities
+    */
+    fn has_powers(&self, role: &Role) -> bool {
+        !(self.village_powers_lost && role.faction() == Some(Faction::Village))
+    }
+
+    /*
+    The closest living players to the left and to the right of a player
+    */
+    fn living_neighbors(&self, player_id: PlayerId) -> Vec<PlayerId> {
+        let position = match self.seating.iter().position(|id| *id == player_id) {
+            Some(position) => position,
+            None => return Vec::new(),
+        };
+        let is_alive =
+            |id: &PlayerId| matches!(self.players.get(id), Some(player) if player.is_alive);
+        let count = self.seating.len();
+        let right = (1..count)
+            .map(|offset| self.seating[(position + offset) % count])
+            .find(is_alive);
+        let left = (1..count)
+            .map(|offset| self.seating[(position + count - offset) % count])
+            .find(is_alive);
+        let mut neighbors: Vec<PlayerId> = left.into_iter().collect();
+        if let Some(right) = right {
+            if !neighbors.contains(&right) {
+                neighbors.push(right);
+            }
         }
+        neighbors
+    }
+}
+
+#[cfg(test)]
+impl GameData {
+    /*
+    Creates the data of a running game in which the players have the given roles and sit in this order.
+    The player with the n-th role has the id n
+    */
+    pub fn with_roles(roles: &[Role]) -> Self {
+        let mut game_data = GameData::default();
+        for (idx, role) in roles.iter().enumerate() {
+            let id = PlayerId::new(idx as u64);
+            let player = Player {
+                name: format!("Player {}", idx),
+                role_data: RoleData::new(role),
+                faction: role.faction(),
+                is_lobby_host: idx == 0,
+                is_alive: role.is_player(),
+                is_connected: true,
+                known_roles: HashMap::new(),
+                status: PlayerStatus::default(),
+            };
+            game_data.players.insert(id, player);
+            game_data.seating.push(id);
+        }
+        game_data
     }
 }
 
@@ -104,8 +260,11 @@ impl GameLobby {
                 game_cancel: cancel_sender,
 
                 clients: HashMap::new(),
+                reconnect_tokens: HashMap::new(),
                 game_data: GameData::default(),
                 game_config: GameConfig::default(),
+                phase: LobbyPhase::Lobby,
+                game_number: 0,
             },
             sender,
         )
@@ -114,57 +273,225 @@ impl GameLobby {
     pub async fn run(&mut self) {
         while let Some(event) = self.receiver.recv().await {
             match event {
-                GameLobbyEvent::NewConnection { ws_read, ws_write } => {
+                GameLobbyEvent::NewConnection {
+                    ws_read,
+                    mut ws_write,
+                    name,
+                } => {
+                    let name = match self.validate_name(&name, None) {
+                        Ok(name) => name,
+                        Err(e) => {
+                            //The client is not part of the lobby yet, so the websocket is used directly
+                            send_logging(&mut ws_write, PacketToClient::RequestRejected(e)).await;
+                            if let Err(e) = ws_write.close().await {
+                                error!("Error closing connection to client: {}", e);
+                            }
+                            continue;
+                        }
+                    };
                     let client_id = generate_id(&self.clients);
+                    let reconnect_token = generate_token();
                     let (client_manager, client_sender) = ClientManager::new(
                         self.id,
                         client_id,
+                        reconnect_token.clone(),
                         ws_write,
                         ws_read,
                         self.sender.clone(),
                     )
                     .await;
                     client_manager.start().await;
-                    let player = Player {
-                        role_data: RoleData::Spectator,
-                        is_lobby_host: self.game_data.players.values().all(|c| !c.is_lobby_host),
-                        is_alive: false,
-                    };
+                    let is_lobby_host = self.game_data.players.values().all(|c| !c.is_lobby_host);
+                    let player = Player::new(name, is_lobby_host);
                     self.game_data.players.insert(client_id, player);
                     self.clients.insert(client_id, client_sender);
+                    self.reconnect_tokens.insert(reconnect_token, client_id);
                     self.send_update().await;
                 }
+                GameLobbyEvent::Reconnect {
+                    ws_read,
+                    mut ws_write,
+                    token,
+                } => {
+                    let client = self
+                        .reconnect_tokens
+                        .get(&token)
+                        .and_then(|client_id| Some((*client_id, self.clients.get(client_id)?)));
+                    match client {
+                        Some((client_id, client_sender)) => {
+                            if client_sender
+                                .send(ClientEvent::Reconnect { ws_read, ws_write })
+                                .await
+                                .is_err()
+                            {
+                                error!("Error sending reconnect to client manager");
+                            }
+                            if let Some(player) = self.game_data.players.get_mut(&client_id) {
+                                player.is_connected = true;
+                            }
+                            self.send_update().await;
+                        }
+                        None => {
+                            send_logging(
+                                &mut ws_write,
+                                PacketToClient::RequestRejected(
+                                    RequestError::InvalidReconnectToken,
+                                ),
+                            )
+                            .await;
+                            if let Err(e) = ws_write.close().await {
+                                error!("Error closing connection to client: {}", e);
+                            }
+                        }
+                    }
+                }
                 GameLobbyEvent::ConnectionLost { client_id } => {
-                    //TODO Consider adding a "reconnect" feature if connection loss becomes a problem
-                    self.clients.remove(&client_id);
+                    if self.phase == LobbyPhase::Lobby {
+                        //There is no game to return to, so the player leaves the lobby
+                        self.remove_player(client_id);
+                    } else if let Some(player) = self.game_data.players.get_mut(&client_id) {
+                        //The client manager and the player are kept, so that the client can reconnect
+                        player.is_connected = false;
+                    }
+                    self.pass_on_lobby_host();
                     self.send_update().await;
                 }
                 GameLobbyEvent::StartGame {
                     requested_by: client_id,
                 } => {
-                    let player = self.game_data.players.get(&client_id).unwrap();
-                    if player.is_lobby_host {
+                    if !self.check_lobby_host(client_id).await {
+                        warn!("Received start game request by client without permission");
+                    } else if self.phase == LobbyPhase::InGame {
+                        self.send_error(client_id, RequestError::GameAlreadyRunning)
+                            .await;
+                    } else if let Err(e) = self.game_config.validate(self.player_count()) {
+                        self.send_error(client_id, RequestError::InvalidGameConfig(e))
+                            .await;
+                    } else {
+                        //Starting a rematch directly from the post game screen is allowed
+                        self.reset_game();
+                        self.phase = LobbyPhase::InGame;
+                        self.game_number += 1;
                         let game_runner = GameRunner::new(
+                            self.game_number,
                             self.game_config.clone(),
                             self.sender.clone(),
                             self.game_cancel.clone(),
                         );
                         game_runner.start().await;
+                    }
+                }
+                GameLobbyEvent::CancelGame {
+                    requested_by: client_id,
+                } => {
+                    if !self.check_lobby_host(client_id).await {
+                        warn!("Received cancel game request by client without permission");
+                    } else if self.phase != LobbyPhase::InGame {
+                        self.send_error(client_id, RequestError::NoGameRunning)
+                            .await;
                     } else {
-                        warn!("Received start game request by client without permission");
+                        //Stop all tasks of the game runner. Events it has already sent are ignored, as the phase changes
+                        self.game_cancel.send(()).ok();
+                        self.return_to_lobby().await;
+                    }
+                }
+                GameLobbyEvent::ReturnToLobby {
+                    requested_by: client_id,
+                } => {
+                    if !self.check_lobby_host(client_id).await {
+                        warn!("Received return to lobby request by client without permission");
+                    } else if self.phase == LobbyPhase::InGame {
+                        self.send_error(client_id, RequestError::GameAlreadyRunning)
+                            .await;
+                    } else {
+                        self.return_to_lobby().await;
+                    }
+                }
+                GameLobbyEvent::UpdateGameConfig {
+                    config,
+                    requested_by: client_id,
+                } => {
+                    if !self.check_lobby_host(client_id).await {
+                        warn!("Received game config update by client without permission");
+                    } else if self.phase == LobbyPhase::InGame {
+                        self.send_error(client_id, RequestError::GameAlreadyRunning)
+                            .await;
+                    } else if let Err(e) = config.validate(self.player_count()) {
+                        self.send_error(client_id, RequestError::InvalidGameConfig(e))
+                            .await;
+                    } else {
+                        self.game_config = config;
+                        self.send_update().await;
+                    }
+                }
+                GameLobbyEvent::SetName {
+                    name,
+                    requested_by: client_id,
+                } => match self.validate_name(&name, Some(client_id)) {
+                    Ok(name) => {
+                        if let Some(player) = self.game_data.players.get_mut(&client_id) {
+                            player.name = name;
+                        }
+                        self.send_update().await;
+                    }
+                    Err(e) => {
+                        self.send_error(client_id, e).await;
+                    }
+                },
+                //All following events are sent by the game runner and are only valid while a game is running
+                _ if self.phase != LobbyPhase::InGame => {
+                    warn!("Received game event while no game is running");
+                }
+                GameLobbyEvent::GameFinished { game_number } => {
+                    //A cancelled game may only finish after the next one has been started
+                    if game_number != self.game_number {
+                        warn!("Received game finished event of an earlier game");
+                        continue;
                     }
+                    self.phase = LobbyPhase::PostGame;
+                    self.send_update().await;
                 }
-                GameLobbyEvent::ApplyDeaths => {
-                    for (player, cause) in mem::take(&mut self.game_data.dying_players) {
-                        self.kill_player(player, cause).await;
+                GameLobbyEvent::KillPlayer(id, cause, died) => {
+                    let role = self.kill_player(id, cause).await;
+                    self.send_update().await;
+                    if died.send(role).is_err() {
+                        error!("Error notifying the game runner of a death");
                     }
                 }
-                GameLobbyEvent::KillPlayer(id, cause) => {
-                    self.kill_player(id, cause).await;
+                GameLobbyEvent::GameOver {
+                    winner,
+                    winning_players,
+                } => {
+                    let roles = self
+                        .game_data
+                        .players
+                        .iter()
+                        .map(|(id, player)| (*id, player.role_data.clone()))
+                        .collect();
+                    self.broadcast(PacketToClient::GameOver {
+                        winner,
+                        winning_players,
+                        roles,
+                    })
+                    .await;
+                }
+                GameLobbyEvent::ChangeRole(id, role_data) => {
+                    match self.game_data.players.get_mut(&id) {
+                        Some(player) => {
+                            player.faction = role_data.get_role().faction();
+                            player.role_data = role_data;
+                        }
+                        None => warn!("Tried to change the role of a non-existing player"),
+                    }
+                    //The new role changes what the player (and possibly others) can see
+                    self.send_update().await;
                 }
                 GameLobbyEvent::SendUpdate => {
                     self.send_update().await;
                 }
+                GameLobbyEvent::Broadcast(packet) => {
+                    self.broadcast(packet).await;
+                }
                 GameLobbyEvent::AccessGameData(f) => {
                     f(&mut self.game_data, &self.clients);
                 }
@@ -206,7 +533,11 @@ impl GameLobby {
     async fn send_update(&mut self) {
         for sender in self.clients.values() {
             if sender
-                .send(ClientEvent::SendUpdate(self.game_data.clone()))
+                .send(ClientEvent::SendUpdate(
+                    self.game_data.clone(),
+                    self.game_config.clone(),
+                    self.phase,
+                ))
                 .await
                 .is_err()
             {
@@ -215,19 +546,227 @@ impl GameLobby {
         }
     }
 
-    async fn kill_player(&mut self, id: PlayerId, cause: CauseOfDeath) {
-        if let Some(player) = self.game_data.players.get_mut(&id) {
+    /*
+    Checks whether the client is the lobby host and notifies the client if it isn't
+    */
+    async fn check_lobby_host(&self, client_id: PlayerId) -> bool {
+        match self.game_data.players.get(&client_id) {
+            Some(player) if player.is_lobby_host => true,
+            _ => {
+                self.send_error(client_id, RequestError::NotLobbyHost).await;
+                false
+            }
+        }
+    }
+
+    /*
+    Checks whether a nickname may be used by a player (or a newly joining client, if no ID is given).
+    Returns the name without surrounding whitespace
+    */
+    fn validate_name(
+        &self,
+        name: &str,
+        client_id: Option<PlayerId>,
+    ) -> Result<String, RequestError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(RequestError::EmptyName);
+        }
+        let is_duplicate = self.game_data.players.iter().any(|(id, player)| {
+            Some(*id) != client_id && player.name.to_lowercase() == name.to_lowercase()
+        });
+        if is_duplicate {
+            return Err(RequestError::DuplicateName);
+        }
+        Ok(name.to_string())
+    }
+
+    /*
+    Resets all players to their pre-game state. Players that have lost their connection leave the lobby
+    */
+    fn reset_game(&mut self) {
+        let disconnected_players: Vec<PlayerId> = self
+            .game_data
+            .players
+            .iter()
+            .filter(|(_, player)| !player.is_connected)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in disconnected_players {
+            self.remove_player(id);
+        }
+        self.pass_on_lobby_host();
+        for player in self.game_data.players.values_mut() {
+            player.role_data = RoleData::Spectator;
+            player.faction = None;
+            player.is_alive = false;
+            player.known_roles.clear();
+            player.status = PlayerStatus::default();
+        }
+        self.game_data.dying_players.clear();
+        self.game_data.protected_players.clear();
+        self.game_data.undealt_cards.clear();
+        self.game_data.seating.clear();
+        self.game_data.village_powers_lost = false;
+        self.game_data.night = 0;
+    }
+
+    /*
+    The number of players that take part in the next game, which doesn't include players that have lost their connection
+    */
+    fn player_count(&self) -> usize {
+        self.game_data
+            .players
+            .values()
+            .filter(|player| player.is_connected)
+            .count()
+    }
+
+    /*
+    Removes a player from the lobby for good, together with their client manager and reconnect token
+    */
+    fn remove_player(&mut self, client_id: PlayerId) {
+        self.game_data.players.remove(&client_id);
+        //Dropping the sender stops the client manager
+        self.clients.remove(&client_id);
+        self.reconnect_tokens.retain(|_, id| *id != client_id);
+    }
+
+    /*
+    Makes one of the connected players the lobby host if the current host has left or lost their connection
+    */
+    fn pass_on_lobby_host(&mut self) {
+        if self
+            .game_data
+            .players
+            .values()
+            .any(|player| player.is_lobby_host && player.is_connected)
+        {
+            return;
+        }
+        let new_host = self
+            .game_data
+            .players
+            .iter()
+            .find(|(_, player)| player.is_connected)
+            .map(|(id, _)| *id);
+        if let Some(new_host) = new_host {
+            for (id, player) in self.game_data.players.iter_mut() {
+                player.is_lobby_host = *id == new_host;
+            }
+        }
+    }
+
+    /*
+    Resets the game and closes all interactions that are still open from the last game
+    */
+    async fn return_to_lobby(&mut self) {
+        self.reset_game();
+        self.phase = LobbyPhase::Lobby;
+        for sender in self.clients.values() {
+            if sender
+                .send(ClientEvent::CloseAllInteractions)
+                .await
+                .is_err()
+            {
+                error!("Error closing interactions of client manager");
+            }
+        }
+        self.send_update().await;
+    }
+
+    /*
+    Sends a packet to all connected clients
+    */
+    async fn broadcast(&self, packet: PacketToClient) {
+        for sender in self.clients.values() {
+            if sender
+                .send(ClientEvent::SendPacket(packet.clone()))
+                .await
+                .is_err()
+            {
+                error!("Error sending packet to client manager");
+            }
+        }
+    }
+
+    /*
+    Notifies a client that one of its requests has been rejected
+    */
+    async fn send_error(&self, client_id: PlayerId, error: RequestError) {
+        if let Some(sender) = self.clients.get(&client_id) {
+            let packet = PacketToClient::RequestRejected(error);
+            if sender.send(ClientEvent::SendPacket(packet)).await.is_err() {
+                error!("Error sending RequestRejected packet to client manager");
+            }
+        }
+    }
+
+    /*
+    Kills a player, unless they are already dead or their role lets them survive this death.
+    Returns the role of the player if they have died
+    */
+    async fn kill_player(&mut self, id: PlayerId, cause: CauseOfDeath) -> Option<Role> {
+        let role = match self.game_data.players.get(&id) {
+            Some(player) if player.is_alive => player.role_data.get_role(),
+            //A player may be dying from several causes, but only dies once
+            Some(_) => return None,
+            None => {
+                warn!("Tried to kill non-existing player");
+                return None;
+            }
+        };
+        //Surviving or reacting to a death may convert players to another faction
+        let factions: HashMap<PlayerId, Option<Faction>> = self
+            .game_data
+            .players
+            .iter()
+            .map(|(id, player)| (*id, player.faction))
+            .collect();
+        let survived = role.intercept_death(&mut self.game_data, id, &cause);
+        if !survived {
+            let revealed_role = visibility::revealed_on_death(&role, &self.game_config);
             for sender in self.clients.values() {
-                let packet =
-                    PacketToClient::PlayerDied(id, cause.clone(), player.role_data.get_role());
+                let packet = PacketToClient::PlayerDied(id, cause.clone(), revealed_role.clone());
                 if sender.send(ClientEvent::SendPacket(packet)).await.is_err() {
                     error!("Error sending PlayerDied packet to client manager");
                 }
             }
-            player.is_alive = false;
-        } else {
-            warn!("Tried to kill non-existing player");
+            if let Some(player) = self.game_data.players.get_mut(&id) {
+                player.is_alive = false;
+                player.status.cause_of_death = Some(cause);
+                //Lovers don't survive the death of each other
+                if let Some(lover) = player.status.lover {
+                    self.game_data
+                        .dying_players
+                        .push((lover, CauseOfDeath::Heartbreak));
+                }
+            }
+            let alive_players: Vec<(PlayerId, Role)> = self
+                .game_data
+                .players
+                .iter()
+                .filter(|(_, player)| player.is_alive)
+                .map(|(id, player)| (*id, player.role_data.get_role()))
+                .collect();
+            for (player_id, player_role) in alive_players {
+                player_role.on_other_death(&mut self.game_data, player_id, id);
+            }
         }
+
+        for (player_id, faction) in factions {
+            let new_faction = match self.game_data.players.get(&player_id) {
+                Some(player) if player.faction != faction => player.faction,
+                _ => continue,
+            };
+            if let (Some(sender), Some(new_faction)) = (self.clients.get(&player_id), new_faction) {
+                let packet = PacketToClient::FactionChanged(new_faction);
+                if sender.send(ClientEvent::SendPacket(packet)).await.is_err() {
+                    error!("Error sending FactionChanged packet to client manager");
+                }
+            }
+        }
+        (!survived).then_some(role)
     }
 }
 
@@ -236,3 +775,65 @@ impl Debug for GameLobbyEvent {
         write!(f, "GameLobbyEvent")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disconnected_players_leave_before_the_next_game() {
+        let (lobby_manager_sender, _) = mpsc::channel(1);
+        let (mut lobby, _) = GameLobby::new(LobbyId::new(0), lobby_manager_sender);
+        lobby.game_data = GameData::with_roles(&[Role::Werewolf, Role::Villager]);
+        lobby.phase = LobbyPhase::PostGame;
+        let host = lobby.game_data.players.get_mut(&PlayerId::new(0)).unwrap();
+        host.is_connected = false;
+
+        lobby.reset_game();
+        let players: Vec<(PlayerId, bool)> = lobby
+            .game_data
+            .players
+            .iter()
+            .map(|(id, player)| (*id, player.is_lobby_host))
+            .collect();
+        assert_eq!(players, vec![(PlayerId::new(1), true)]);
+    }
+
+    #[tokio::test]
+    async fn earlier_games_dont_finish_the_running_one() {
+        let (lobby_manager_sender, _) = mpsc::channel(1);
+        let (mut lobby, lobby_sender) = GameLobby::new(LobbyId::new(0), lobby_manager_sender);
+        lobby.game_data = GameData::with_roles(&[Role::Werewolf, Role::Villager]);
+        lobby.phase = LobbyPhase::InGame;
+        lobby.game_number = 2;
+        tokio::spawn(async move { lobby.run().await });
+
+        lobby_sender
+            .send(GameLobbyEvent::GameFinished { game_number: 1 })
+            .await
+            .unwrap();
+        let still_running = GameLobby::access_game_data(&lobby_sender, |_, _| ()).await;
+        assert!(still_running.is_ok());
+    }
+
+    #[test]
+    fn dead_neighbors_are_skipped() {
+        let mut game_data = GameData::with_roles(&[
+            Role::Fox,
+            Role::Villager,
+            Role::Werewolf,
+            Role::Villager,
+            Role::Seer,
+        ]);
+        let ids: Vec<PlayerId> = (0..5).map(PlayerId::new).collect();
+        assert_eq!(game_data.living_neighbors(ids[0]), vec![ids[4], ids[1]]);
+        for id in [ids[1], ids[4]].iter() {
+            game_data.players.get_mut(id).unwrap().is_alive = false;
+        }
+        assert_eq!(game_data.living_neighbors(ids[0]), vec![ids[3], ids[2]]);
+        for id in [ids[2], ids[3]].iter() {
+            game_data.players.get_mut(id).unwrap().is_alive = false;
+        }
+        assert_eq!(game_data.living_neighbors(ids[0]), Vec::new());
+    }
+}
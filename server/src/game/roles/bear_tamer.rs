@@ -0,0 +1,42 @@
+use crate::game::{GameData, GameLobbyEvent};
+
+use super::ServerRole;
+use anyhow::Error;
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+use werewolf_rs::{
+    game::{Faction, GameConfig},
+    packet::Announcement,
+    util::PlayerId,
+};
+
+pub struct BearTamer;
+
+#[async_trait]
+impl ServerRole for BearTamer {
+    async fn run_night_turn(
+        &self,
+        _: &mpsc::Sender<GameLobbyEvent>,
+        _: &GameConfig,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /*
+    Each morning, the bear growls if a werewolf sits next to the bear tamer
+    */
+    fn morning_announcement(
+        &self,
+        game_data: &GameData,
+        player_id: PlayerId,
+    ) -> Option<Announcement> {
+        let growls = game_data.living_neighbors(player_id).iter().any(|id| {
+            game_data.players.get(id).map(|player| player.faction)
+                == Some(Some(Faction::Werewolves))
+        });
+        Some(Announcement::BearGrowls {
+            bear_tamer: player_id,
+            growls,
+        })
+    }
+}
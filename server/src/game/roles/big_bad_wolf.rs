@@ -0,0 +1,94 @@
+use crate::game::{
+    deadline::Deadline, interaction::select_target, GameLobby, GameLobbyEvent, Player,
+};
+
+use super::ServerRole;
+use anyhow::Error;
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use tokio::sync::mpsc;
+use werewolf_rs::{
+    game::{CauseOfDeath, Faction, GameConfig, Role},
+    packet::TargetPurpose,
+    util::PlayerId,
+};
+
+pub struct BigBadWolf;
+
+#[async_trait]
+impl ServerRole for BigBadWolf {
+    /*
+    After the vote of the pack, the big bad wolf may kill another player, as long as no werewolf has died
+    */
+    async fn run_night_turn(
+        &self,
+        lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+        game_config: &GameConfig,
+    ) -> Result<(), Error> {
+        let (big_bad_wolves, selectable): (Vec<PlayerId>, Vec<PlayerId>) =
+            GameLobby::access_game_data(lobby_sender, |game_data, _| {
+                let is_werewolf = |player: &Player| player.faction == Some(Faction::Werewolves);
+                let werewolf_died = game_data
+                    .players
+                    .values()
+                    .any(|player| !player.is_alive && is_werewolf(player));
+                if werewolf_died {
+                    return (Vec::new(), Vec::new());
+                }
+                //The victim of the pack can't be chosen again
+                let selectable = game_data
+                    .players
+                    .iter()
+                    .filter(|(id, player)| {
+                        player.is_alive
+                            && !is_werewolf(player)
+                            && !game_data
+                                .dying_players
+                                .iter()
+                                .any(|(dying, _)| dying == *id)
+                    })
+                    .map(|(id, _)| *id)
+                    .collect();
+                let big_bad_wolves = game_data
+                    .players
+                    .iter()
+                    .filter(|(_, player)| {
+                        player.is_alive && player.role_data.get_role() == Role::BigBadWolf
+                    })
+                    .map(|(id, _)| *id)
+                    .collect();
+                (big_bad_wolves, selectable)
+            })
+            .await?;
+        if selectable.is_empty() {
+            return Ok(());
+        }
+
+        let deadline = Deadline::from_seconds(game_config.durations.night_action);
+        try_join_all(big_bad_wolves.into_iter().map(|big_bad_wolf| {
+            let selectable = selectable.clone();
+            async move {
+                let (interaction, target) = select_target(
+                    lobby_sender,
+                    big_bad_wolf,
+                    TargetPurpose::BigBadWolfKill,
+                    selectable,
+                    deadline,
+                )
+                .await?;
+                interaction.close().await?;
+                if let Some(target) = target {
+                    GameLobby::access_game_data(lobby_sender, move |game_data, _| {
+                        game_data
+                            .dying_players
+                            .push((target, CauseOfDeath::Werewolves));
+                    })
+                    .await?;
+                }
+                Ok::<(), Error>(())
+            }
+        }))
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+use crate::game::{deadline::Deadline, interaction::select_target, GameLobby, GameLobbyEvent};
+
+use super::{living_players, ServerRole};
+use anyhow::Error;
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use tokio::sync::mpsc;
+use werewolf_rs::{
+    game::{GameConfig, RoleData},
+    packet::TargetPurpose,
+    util::PlayerId,
+};
+
+pub struct Bodyguard;
+
+#[async_trait]
+impl ServerRole for Bodyguard {
+    async fn run_night_turn(
+        &self,
+        lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+        game_config: &GameConfig,
+    ) -> Result<(), Error> {
+        //The bodyguard may protect themselves, but not the player they protected in the last night
+        let bodyguards: Vec<(PlayerId, Vec<PlayerId>)> =
+            GameLobby::access_game_data(lobby_sender, |game_data, _| {
+                let alive = living_players(game_data);
+                game_data
+                    .players
+                    .iter()
+                    .filter(|(_, player)| player.is_alive)
+                    .filter_map(|(id, player)| match player.role_data {
+                        RoleData::Bodyguard { last_protected } => Some((
+                            *id,
+                            alive
+                                .iter()
+                                .copied()
+                                .filter(|p| Some(*p) != last_protected)
+                                .collect(),
+                        )),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .await?;
+
+        let deadline = Deadline::from_seconds(game_config.durations.night_action);
+        try_join_all(
+            bodyguards.into_iter().map(|(bodyguard, selectable)| {
+                protect(lobby_sender, bodyguard, selectable, deadline)
+            }),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/*
+Lets a bodyguard choose a player that is protected from the werewolves in this night
+*/
+async fn protect(
+    lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+    bodyguard: PlayerId,
+    selectable: Vec<PlayerId>,
+    deadline: Option<Deadline>,
+) -> Result<(), Error> {
+    let (interaction, target) = select_target(
+        lobby_sender,
+        bodyguard,
+        TargetPurpose::BodyguardProtection,
+        selectable,
+        deadline,
+    )
+    .await?;
+    interaction.close().await?;
+    GameLobby::access_game_data(lobby_sender, move |game_data, _| {
+        //The protection is resolved at the end of the night, when all attacks are known
+        if let Some(target) = target {
+            game_data.protected_players.push(target);
+        }
+        if let Some(RoleData::Bodyguard { last_protected }) = game_data
+            .players
+            .get_mut(&bodyguard)
+            .map(|player| &mut player.role_data)
+        {
+            *last_protected = target;
+        }
+    })
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use crate::game::{deadline::Deadline, interaction::select_target, GameLobby, GameLobbyEvent};
+
+use super::ServerRole;
+use anyhow::Error;
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use tokio::sync::mpsc;
+use werewolf_rs::{
+    game::{GameConfig, Role, RoleInfo},
+    packet::TargetPurpose,
+    util::PlayerId,
+};
+
+pub struct Cupid;
+
+#[async_trait]
+impl ServerRole for Cupid {
+    async fn run_night_turn(
+        &self,
+        lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+        game_config: &GameConfig,
+    ) -> Result<(), Error> {
+        //Cupid may choose themselves as one of the lovers
+        let (cupids, selectable): (Vec<PlayerId>, Vec<PlayerId>) =
+            GameLobby::access_game_data(lobby_sender, |game_data, _| {
+                let alive = game_data
+                    .players
+                    .iter()
+                    .filter(|(_, player)| player.is_alive);
+                (
+                    alive
+                        .clone()
+                        .filter(|(_, player)| player.role_data.get_role() == Role::Cupid)
+                        .map(|(id, _)| *id)
+                        .collect(),
+                    alive.map(|(id, _)| *id).collect(),
+                )
+            })
+            .await?;
+
+        let deadline = Deadline::from_seconds(game_config.durations.night_action);
+        try_join_all(
+            cupids
+                .into_iter()
+                .map(|cupid| link_lovers(lobby_sender, cupid, selectable.clone(), deadline)),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/*
+Lets cupid choose two players, who fall in love with each other and learn about each other's roles
+*/
+async fn link_lovers(
+    lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+    cupid: PlayerId,
+    mut selectable: Vec<PlayerId>,
+    deadline: Option<Deadline>,
+) -> Result<(), Error> {
+    let (interaction, first_lover) = select_target(
+        lobby_sender,
+        cupid,
+        TargetPurpose::CupidFirstLover,
+        selectable.clone(),
+        deadline,
+    )
+    .await?;
+    interaction.close().await?;
+    let first_lover = match first_lover {
+        Some(first_lover) => first_lover,
+        None => return Ok(()),
+    };
+    selectable.retain(|id| *id != first_lover);
+    let (interaction, second_lover) = select_target(
+        lobby_sender,
+        cupid,
+        TargetPurpose::CupidSecondLover,
+        selectable,
+        deadline,
+    )
+    .await?;
+    interaction.close().await?;
+    let second_lover = match second_lover {
+        Some(second_lover) => second_lover,
+        None => return Ok(()),
+    };
+
+    GameLobby::access_game_data(lobby_sender, move |game_data, _| {
+        for (lover, other) in [(first_lover, second_lover), (second_lover, first_lover)] {
+            let other_role = match game_data.players.get(&other) {
+                Some(player) => player.role_data.get_role(),
+                None => continue,
+            };
+            if let Some(player) = game_data.players.get_mut(&lover) {
+                player.status.lover = Some(other);
+                player
+                    .known_roles
+                    .insert(other, RoleInfo::Known(other_role));
+            }
+        }
+    })
+    .await?;
+    lobby_sender.send(GameLobbyEvent::SendUpdate).await?;
+    Ok(())
+}
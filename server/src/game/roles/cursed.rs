@@ -0,0 +1,48 @@
+use crate::game::{GameData, GameLobbyEvent};
+
+use super::ServerRole;
+use anyhow::Error;
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+use werewolf_rs::{
+    game::{CauseOfDeath, Faction, GameConfig, RoleData},
+    util::PlayerId,
+};
+
+pub struct Cursed;
+
+#[async_trait]
+impl ServerRole for Cursed {
+    async fn run_night_turn(
+        &self,
+        _: &mpsc::Sender<GameLobbyEvent>,
+        _: &GameConfig,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /*
+    Instead of dying, the cursed villager joins the werewolves that attacked them
+    */
+    fn intercept_death(
+        &self,
+        game_data: &mut GameData,
+        player_id: PlayerId,
+        cause: &CauseOfDeath,
+    ) -> bool {
+        if !matches!(cause, CauseOfDeath::Werewolves) {
+            return false;
+        }
+        match game_data.players.get_mut(&player_id) {
+            Some(player) => match &mut player.role_data {
+                RoleData::Cursed { transformed } if !*transformed => {
+                    *transformed = true;
+                    player.faction = Some(Faction::Werewolves);
+                    true
+                }
+                _ => false,
+            },
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use crate::game::{GameData, GameLobbyEvent};
+
+use super::ServerRole;
+use anyhow::Error;
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+use werewolf_rs::{
+    game::{CauseOfDeath, GameConfig, RoleData},
+    util::PlayerId,
+};
+
+pub struct Elder;
+
+#[async_trait]
+impl ServerRole for Elder {
+    async fn run_night_turn(
+        &self,
+        _: &mpsc::Sender<GameLobbyEvent>,
+        _: &GameConfig,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /*
+    The elder survives the first attack of the werewolves.
+    If the village lynches the elder, all villagers lose their special abilities for the rest of the game
+    */
+    fn intercept_death(
+        &self,
+        game_data: &mut GameData,
+        player_id: PlayerId,
+        cause: &CauseOfDeath,
+    ) -> bool {
+        match cause {
+            CauseOfDeath::Werewolves => match game_data.players.get_mut(&player_id) {
+                Some(player) => match &mut player.role_data {
+                    RoleData::Elder { attacked } if !*attacked => {
+                        *attacked = true;
+                        true
+                    }
+                    _ => false,
+                },
+                None => false,
+            },
+            CauseOfDeath::VillageVote => {
+                game_data.village_powers_lost = true;
+                false
+            }
+            _ => false,
+        }
+    }
+}
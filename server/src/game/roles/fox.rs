@@ -0,0 +1,86 @@
+use crate::game::{deadline::Deadline, interaction::select_target, GameLobby, GameLobbyEvent};
+
+use super::{living_players, targets_by_player, ServerRole};
+use anyhow::Error;
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use tokio::sync::mpsc;
+use werewolf_rs::{
+    game::{Faction, GameConfig, RoleData},
+    packet::{InteractionFollowup, TargetPurpose},
+    util::PlayerId,
+};
+
+pub struct Fox;
+
+#[async_trait]
+impl ServerRole for Fox {
+    async fn run_night_turn(
+        &self,
+        lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+        game_config: &GameConfig,
+    ) -> Result<(), Error> {
+        //Only foxes that haven't missed yet can sniff
+        let foxes: Vec<(PlayerId, Vec<PlayerId>)> =
+            GameLobby::access_game_data(lobby_sender, |game_data, _| {
+                let has_power =
+                    |role_data: &RoleData| matches!(role_data, RoleData::Fox { has_power: true });
+                targets_by_player(game_data, has_power, &living_players(game_data))
+            })
+            .await?;
+
+        let deadline = Deadline::from_seconds(game_config.durations.night_action);
+        try_join_all(
+            foxes
+                .into_iter()
+                .map(|(fox, selectable)| sniff(lobby_sender, fox, selectable, deadline)),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/*
+Lets a fox choose a player and tells them whether that player or one of their neighbors is a werewolf.
+The fox loses their power if there is no werewolf among them
+*/
+async fn sniff(
+    lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+    fox: PlayerId,
+    selectable: Vec<PlayerId>,
+    deadline: Option<Deadline>,
+) -> Result<(), Error> {
+    let (interaction, target) = select_target(
+        lobby_sender,
+        fox,
+        TargetPurpose::FoxSniff,
+        selectable,
+        deadline,
+    )
+    .await?;
+    if let Some(target) = target {
+        let werewolf_nearby = GameLobby::access_game_data(lobby_sender, move |game_data, _| {
+            let mut suspects = game_data.living_neighbors(target);
+            suspects.push(target);
+            let werewolf_nearby = suspects.iter().any(|id| {
+                game_data.players.get(id).map(|player| player.faction)
+                    == Some(Some(Faction::Werewolves))
+            });
+            if !werewolf_nearby {
+                if let Some(player) = game_data.players.get_mut(&fox) {
+                    player.role_data = RoleData::Fox { has_power: false };
+                }
+            }
+            werewolf_nearby
+        })
+        .await?;
+        interaction
+            .followup(InteractionFollowup::TsFoxResult {
+                target,
+                werewolf_nearby,
+            })
+            .await?;
+        lobby_sender.send(GameLobbyEvent::SendUpdate).await?;
+    }
+    interaction.close().await
+}
@@ -0,0 +1,66 @@
+use crate::game::{deadline::Deadline, interaction::select_target, GameLobby, GameLobbyEvent};
+
+use super::{living_players, ServerRole};
+use anyhow::Error;
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+use werewolf_rs::{
+    game::{CauseOfDeath, GameConfig},
+    packet::TargetPurpose,
+    util::PlayerId,
+};
+
+pub struct Hunter;
+
+#[async_trait]
+impl ServerRole for Hunter {
+    async fn run_night_turn(
+        &self,
+        _: &mpsc::Sender<GameLobbyEvent>,
+        _: &GameConfig,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /*
+    With their last breath, the hunter may shoot another living player
+    */
+    async fn on_death(
+        &self,
+        lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+        game_config: &GameConfig,
+        player_id: PlayerId,
+    ) -> Result<(), Error> {
+        let selectable: Vec<PlayerId> =
+            GameLobby::access_game_data(lobby_sender, move |game_data, _| {
+                living_players(game_data)
+                    .into_iter()
+                    .filter(|id| *id != player_id)
+                    .collect()
+            })
+            .await?;
+        if selectable.is_empty() {
+            return Ok(());
+        }
+
+        let deadline = Deadline::from_seconds(game_config.durations.night_action);
+        let (interaction, target) = select_target(
+            lobby_sender,
+            player_id,
+            TargetPurpose::HunterShot,
+            selectable,
+            deadline,
+        )
+        .await?;
+        interaction.close().await?;
+        if let Some(target) = target {
+            GameLobby::access_game_data(lobby_sender, move |game_data, _| {
+                game_data
+                    .dying_players
+                    .push((target, CauseOfDeath::HunterShot));
+            })
+            .await?;
+        }
+        Ok(())
+    }
+}
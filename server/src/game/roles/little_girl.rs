@@ -0,0 +1,18 @@
+use super::ServerRole;
+use async_trait::async_trait;
+
+/*
+The little girl acts during the turn of the werewolves, which is handled by the werewolf role
+*/
+pub struct LittleGirl;
+
+#[async_trait]
+impl ServerRole for LittleGirl {
+    async fn run_night_turn(
+        &self,
+        _: &tokio::sync::mpsc::Sender<crate::game::GameLobbyEvent>,
+        _: &werewolf_rs::game::GameConfig,
+    ) -> Result<(), anyhow::Error> {
+        Ok(())
+    }
+}
@@ -1,13 +1,35 @@
+mod bear_tamer;
+mod big_bad_wolf;
+mod bodyguard;
+mod cupid;
+mod cursed;
+mod elder;
+mod fox;
+mod hunter;
+mod little_girl;
+mod seer;
+mod serial_killer;
+mod siblings;
 mod spectator;
+mod tanner;
+mod thief;
+mod village_idiot;
 mod villager;
 mod werewolf;
+mod white_werewolf;
+mod wild_child;
+mod witch;
 
 use anyhow::Error;
 use async_trait::async_trait;
 use tokio::sync::mpsc;
-use werewolf_rs::game::Role;
+use werewolf_rs::{
+    game::{CauseOfDeath, GameConfig, Role, RoleData},
+    packet::Announcement,
+    util::PlayerId,
+};
 
-use super::GameLobbyEvent;
+use super::{GameData, GameLobbyEvent};
 
 /*
 A trait for the server logic of a role
@@ -17,7 +39,57 @@ pub trait ServerRole {
     async fn run_night_turn(
         &self,
         lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+        game_config: &GameConfig,
     ) -> Result<(), Error>;
+    /*
+    Called after a player with this role has died, before the game continues.
+    Further deaths can be caused by adding them to the dying players
+    */
+    async fn on_death(
+        &self,
+        _lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+        _game_config: &GameConfig,
+        _player_id: PlayerId,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+    /*
+    Called when a player with this role is about to die. Returns true if the player survives
+    */
+    fn intercept_death(
+        &self,
+        _game_data: &mut GameData,
+        _player_id: PlayerId,
+        _cause: &CauseOfDeath,
+    ) -> bool {
+        false
+    }
+    /*
+    Called for every living player with this role after another player has died
+    */
+    fn on_other_death(
+        &self,
+        _game_data: &mut GameData,
+        _player_id: PlayerId,
+        _dead_player: PlayerId,
+    ) {
+    }
+    /*
+    A public announcement that a living player with this role makes in the morning
+    */
+    fn morning_announcement(
+        &self,
+        _game_data: &GameData,
+        _player_id: PlayerId,
+    ) -> Option<Announcement> {
+        None
+    }
+    /*
+    Whether a player with this role has won the game alone, which ends the game immediately
+    */
+    fn has_won(&self, _game_data: &GameData, _player_id: PlayerId) -> bool {
+        false
+    }
 }
 
 /*
@@ -30,8 +102,45 @@ pub trait ServerRoleDelegator {
     async fn run_night_turn(
         &self,
         lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+        game_config: &GameConfig,
+    ) -> Result<(), Error> {
+        self.get_implementor()
+            .run_night_turn(lobby_sender, game_config)
+            .await
+    }
+    async fn on_death(
+        &self,
+        lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+        game_config: &GameConfig,
+        player_id: PlayerId,
     ) -> Result<(), Error> {
-        self.get_implementor().run_night_turn(lobby_sender).await
+        self.get_implementor()
+            .on_death(lobby_sender, game_config, player_id)
+            .await
+    }
+    fn intercept_death(
+        &self,
+        game_data: &mut GameData,
+        player_id: PlayerId,
+        cause: &CauseOfDeath,
+    ) -> bool {
+        self.get_implementor()
+            .intercept_death(game_data, player_id, cause)
+    }
+    fn on_other_death(&self, game_data: &mut GameData, player_id: PlayerId, dead_player: PlayerId) {
+        self.get_implementor()
+            .on_other_death(game_data, player_id, dead_player)
+    }
+    fn morning_announcement(
+        &self,
+        game_data: &GameData,
+        player_id: PlayerId,
+    ) -> Option<Announcement> {
+        self.get_implementor()
+            .morning_announcement(game_data, player_id)
+    }
+    fn has_won(&self, game_data: &GameData, player_id: PlayerId) -> bool {
+        self.get_implementor().has_won(game_data, player_id)
     }
 }
 
@@ -42,6 +151,67 @@ impl ServerRoleDelegator for Role {
             Role::Spectator => Box::new(spectator::Spectator),
             Role::Villager => Box::new(villager::Villager),
             Role::Werewolf => Box::new(werewolf::Werewolf),
+            Role::Seer => Box::new(seer::Seer),
+            Role::Witch => Box::new(witch::Witch),
+            Role::Hunter => Box::new(hunter::Hunter),
+            Role::Cupid => Box::new(cupid::Cupid),
+            Role::Bodyguard => Box::new(bodyguard::Bodyguard),
+            Role::LittleGirl => Box::new(little_girl::LittleGirl),
+            Role::Thief => Box::new(thief::Thief),
+            Role::VillageIdiot => Box::new(village_idiot::VillageIdiot),
+            Role::Elder => Box::new(elder::Elder),
+            Role::WhiteWerewolf => Box::new(white_werewolf::WhiteWerewolf),
+            Role::BigBadWolf => Box::new(big_bad_wolf::BigBadWolf),
+            Role::Tanner => Box::new(tanner::Tanner),
+            Role::SerialKiller => Box::new(serial_killer::SerialKiller),
+            Role::WildChild => Box::new(wild_child::WildChild),
+            Role::Cursed => Box::new(cursed::Cursed),
+            Role::Sister => Box::new(siblings::Sister),
+            Role::Brother => Box::new(siblings::Brother),
+            Role::Fox => Box::new(fox::Fox),
+            Role::BearTamer => Box::new(bear_tamer::BearTamer),
         }
     }
 }
+
+/*
+Whether the given player is the only one left alive
+*/
+fn last_alive(game_data: &GameData, player_id: PlayerId) -> bool {
+    game_data
+        .players
+        .iter()
+        .all(|(id, player)| player.is_alive == (*id == player_id))
+}
+
+/*
+The ids of all living players
+*/
+fn living_players(game_data: &GameData) -> Vec<PlayerId> {
+    game_data
+        .players
+        .iter()
+        .filter(|(_, player)| player.is_alive)
+        .map(|(id, _)| *id)
+        .collect()
+}
+
+/*
+Pairs every living player whose role data matches with the candidates they can choose as their target.
+A player can't choose themselves
+*/
+fn targets_by_player(
+    game_data: &GameData,
+    has_turn: impl Fn(&RoleData) -> bool,
+    candidates: &[PlayerId],
+) -> Vec<(PlayerId, Vec<PlayerId>)> {
+    game_data
+        .players
+        .iter()
+        .filter(|(_, player)| player.is_alive && has_turn(&player.role_data))
+        .map(|(id, _)| {
+            let targets = candidates.iter().copied().filter(|p| p != id).collect();
+            (*id, targets)
+        })
+        .collect()
+}
@@ -0,0 +1,88 @@
+use crate::game::{deadline::Deadline, interaction::select_target, GameLobby, GameLobbyEvent};
+
+use super::{living_players, targets_by_player, ServerRole};
+use anyhow::Error;
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use tokio::sync::mpsc;
+use werewolf_rs::{
+    game::{GameConfig, Role, RoleData, RoleInfo, SeerReveal},
+    packet::{InteractionFollowup, TargetPurpose},
+    util::PlayerId,
+};
+
+pub struct Seer;
+
+#[async_trait]
+impl ServerRole for Seer {
+    async fn run_night_turn(
+        &self,
+        lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+        game_config: &GameConfig,
+    ) -> Result<(), Error> {
+        //Every alive seer may inspect one other alive player
+        let seers: Vec<(PlayerId, Vec<PlayerId>)> =
+            GameLobby::access_game_data(lobby_sender, |game_data, _| {
+                let is_seer = |role_data: &RoleData| role_data.get_role() == Role::Seer;
+                targets_by_player(game_data, is_seer, &living_players(game_data))
+            })
+            .await?;
+
+        let deadline = Deadline::from_seconds(game_config.durations.night_action);
+        try_join_all(seers.into_iter().map(|(seer, selectable)| {
+            inspect(
+                lobby_sender,
+                seer,
+                selectable,
+                deadline,
+                game_config.seer_reveals.clone(),
+            )
+        }))
+        .await?;
+        Ok(())
+    }
+}
+
+/*
+Lets a seer choose a player and privately reveals the role (or faction) of that player to them
+*/
+async fn inspect(
+    lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+    seer: PlayerId,
+    selectable: Vec<PlayerId>,
+    deadline: Option<Deadline>,
+    seer_reveals: SeerReveal,
+) -> Result<(), Error> {
+    let (interaction, target) = select_target(
+        lobby_sender,
+        seer,
+        TargetPurpose::SeerInspection,
+        selectable,
+        deadline,
+    )
+    .await?;
+    if let Some(target) = target {
+        //The knowledge is stored with the seer, so that it is part of their GameInfo from now on
+        let role_info = GameLobby::access_game_data(lobby_sender, move |game_data, _| {
+            let target_player = game_data.players.get(&target)?;
+            let role_info = match seer_reveals {
+                SeerReveal::Role => RoleInfo::Known(target_player.role_data.get_role()),
+                SeerReveal::Faction => RoleInfo::KnownFaction(target_player.faction?),
+            };
+            game_data
+                .players
+                .get_mut(&seer)?
+                .known_roles
+                .insert(target, role_info.clone());
+            Some(role_info)
+        })
+        .await?;
+        if let Some(role_info) = role_info {
+            interaction
+                .followup(InteractionFollowup::TsSeerResult { target, role_info })
+                .await?;
+            lobby_sender.send(GameLobbyEvent::SendUpdate).await?;
+        }
+    }
+    interaction.close().await
+}
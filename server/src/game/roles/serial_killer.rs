@@ -0,0 +1,67 @@
+use crate::game::{
+    deadline::Deadline, interaction::select_target, GameData, GameLobby, GameLobbyEvent,
+};
+
+use super::{last_alive, living_players, targets_by_player, ServerRole};
+use anyhow::Error;
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use tokio::sync::mpsc;
+use werewolf_rs::{
+    game::{CauseOfDeath, GameConfig, Role, RoleData},
+    packet::TargetPurpose,
+    util::PlayerId,
+};
+
+pub struct SerialKiller;
+
+#[async_trait]
+impl ServerRole for SerialKiller {
+    /*
+    The serial killer kills one player each night, independently of the werewolves
+    */
+    async fn run_night_turn(
+        &self,
+        lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+        game_config: &GameConfig,
+    ) -> Result<(), Error> {
+        let killers: Vec<(PlayerId, Vec<PlayerId>)> =
+            GameLobby::access_game_data(lobby_sender, |game_data, _| {
+                let is_serial_killer =
+                    |role_data: &RoleData| role_data.get_role() == Role::SerialKiller;
+                targets_by_player(game_data, is_serial_killer, &living_players(game_data))
+            })
+            .await?;
+
+        let deadline = Deadline::from_seconds(game_config.durations.night_action);
+        try_join_all(killers.into_iter().map(|(killer, selectable)| async move {
+            let (interaction, target) = select_target(
+                lobby_sender,
+                killer,
+                TargetPurpose::SerialKillerKill,
+                selectable,
+                deadline,
+            )
+            .await?;
+            interaction.close().await?;
+            if let Some(target) = target {
+                GameLobby::access_game_data(lobby_sender, move |game_data, _| {
+                    game_data
+                        .dying_players
+                        .push((target, CauseOfDeath::SerialKiller));
+                })
+                .await?;
+            }
+            Ok::<(), Error>(())
+        }))
+        .await?;
+        Ok(())
+    }
+
+    /*
+    The serial killer wins if they are the last one alive
+    */
+    fn has_won(&self, game_data: &GameData, player_id: PlayerId) -> bool {
+        last_alive(game_data, player_id)
+    }
+}
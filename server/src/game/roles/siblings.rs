@@ -0,0 +1,60 @@
+use crate::game::{deadline::Deadline, interaction::group_chat, GameLobby, GameLobbyEvent};
+
+use super::ServerRole;
+use anyhow::Error;
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+use werewolf_rs::{
+    game::{GameConfig, Role},
+    util::PlayerId,
+};
+
+pub struct Sister;
+pub struct Brother;
+
+#[async_trait]
+impl ServerRole for Sister {
+    async fn run_night_turn(
+        &self,
+        lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+        game_config: &GameConfig,
+    ) -> Result<(), Error> {
+        meet(lobby_sender, game_config, Role::Sister).await
+    }
+}
+
+#[async_trait]
+impl ServerRole for Brother {
+    async fn run_night_turn(
+        &self,
+        lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+        game_config: &GameConfig,
+    ) -> Result<(), Error> {
+        meet(lobby_sender, game_config, Role::Brother).await
+    }
+}
+
+/*
+The living siblings wake up together and talk privately
+*/
+async fn meet(
+    lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+    game_config: &GameConfig,
+    role: Role,
+) -> Result<(), Error> {
+    let siblings: Vec<PlayerId> = GameLobby::access_game_data(lobby_sender, move |game_data, _| {
+        game_data
+            .players
+            .iter()
+            .filter(|(_, player)| player.is_alive && player.role_data.get_role() == role)
+            .map(|(id, _)| *id)
+            .collect()
+    })
+    .await?;
+    //There is no one to talk to for the last sibling alive
+    if siblings.len() < 2 {
+        return Ok(());
+    }
+    let deadline = Deadline::from_seconds(game_config.durations.night_action);
+    group_chat(lobby_sender, siblings, deadline).await
+}
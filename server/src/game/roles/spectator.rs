@@ -8,6 +8,7 @@ impl ServerRole for Spectator {
     async fn run_night_turn(
         &self,
         _: &tokio::sync::mpsc::Sender<crate::game::GameLobbyEvent>,
+        _: &werewolf_rs::game::GameConfig,
     ) -> Result<(), anyhow::Error> {
         Ok(())
     }
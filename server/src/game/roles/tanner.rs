@@ -0,0 +1,34 @@
+use crate::game::{GameData, GameLobbyEvent};
+
+use super::ServerRole;
+use anyhow::Error;
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+use werewolf_rs::{
+    game::{CauseOfDeath, GameConfig},
+    util::PlayerId,
+};
+
+pub struct Tanner;
+
+#[async_trait]
+impl ServerRole for Tanner {
+    async fn run_night_turn(
+        &self,
+        _: &mpsc::Sender<GameLobbyEvent>,
+        _: &GameConfig,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /*
+    The tanner wins as soon as the village lynches them
+    */
+    fn has_won(&self, game_data: &GameData, player_id: PlayerId) -> bool {
+        let cause_of_death = game_data
+            .players
+            .get(&player_id)
+            .and_then(|player| player.status.cause_of_death.as_ref());
+        matches!(cause_of_death, Some(CauseOfDeath::VillageVote))
+    }
+}
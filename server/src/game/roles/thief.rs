@@ -0,0 +1,108 @@
+use crate::game::{deadline::Deadline, interaction::SingleInteraction, GameLobby, GameLobbyEvent};
+
+use super::ServerRole;
+use anyhow::Error;
+use async_trait::async_trait;
+use rand::Rng;
+use tokio::sync::mpsc;
+use werewolf_rs::{
+    game::{Faction, GameConfig, Role, RoleData},
+    packet::{InteractionRequest, InteractionResponse},
+    util::PlayerId,
+};
+
+pub struct Thief;
+
+#[async_trait]
+impl ServerRole for Thief {
+    async fn run_night_turn(
+        &self,
+        lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+        game_config: &GameConfig,
+    ) -> Result<(), Error> {
+        let thieves: Vec<PlayerId> = GameLobby::access_game_data(lobby_sender, |game_data, _| {
+            game_data
+                .players
+                .iter()
+                .filter(|(_, player)| player.is_alive && player.role_data.get_role() == Role::Thief)
+                .map(|(id, _)| *id)
+                .collect()
+        })
+        .await?;
+
+        let deadline = Deadline::from_seconds(game_config.durations.night_action);
+        //The thieves choose one after another, as they take their cards from the same pile
+        for thief in thieves {
+            steal_card(lobby_sender, thief, deadline).await?;
+        }
+        Ok(())
+    }
+}
+
+/*
+Lets a thief take one of the undealt cards, which becomes their role for the rest of the game.
+The thief has to take a card if all of them are werewolves
+*/
+async fn steal_card(
+    lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+    thief: PlayerId,
+    deadline: Option<Deadline>,
+) -> Result<(), Error> {
+    let cards: Vec<Role> =
+        GameLobby::access_game_data(lobby_sender, |game_data, _| game_data.undealt_cards.clone())
+            .await?;
+    if cards.is_empty() {
+        return Ok(());
+    }
+    let mandatory = cards
+        .iter()
+        .all(|card| card.faction() == Some(Faction::Werewolves));
+
+    let mut interaction = SingleInteraction::create(
+        lobby_sender,
+        thief,
+        InteractionRequest::CsBegin {
+            cards: cards.clone(),
+            mandatory,
+            deadline: Deadline::timestamp(&deadline),
+        },
+    )
+    .await?;
+    let mut choice = None;
+    while let Some(response) = interaction.receive(&deadline).await {
+        match response {
+            InteractionResponse::CsSelect { card: Some(card) } if card < cards.len() => {
+                choice = Some(card);
+                break;
+            }
+            InteractionResponse::CsSelect { card: None } if !mandatory => break,
+            r => {
+                warn!(
+                    "Received invalid interaction response during card selection: {:?}",
+                    r
+                );
+            }
+        }
+    }
+    interaction.close().await?;
+    if choice.is_none() && mandatory {
+        choice = Some(rand::thread_rng().gen_range(0..cards.len()));
+    }
+
+    if let Some(card) = choice {
+        GameLobby::access_game_data(lobby_sender, move |game_data, _| {
+            //The thief card takes the place of the stolen card
+            if let Some(undealt_card) = game_data.undealt_cards.get_mut(card) {
+                *undealt_card = Role::Thief;
+            }
+        })
+        .await?;
+        lobby_sender
+            .send(GameLobbyEvent::ChangeRole(
+                thief,
+                RoleData::new(&cards[card]),
+            ))
+            .await?;
+    }
+    Ok(())
+}
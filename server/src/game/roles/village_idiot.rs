@@ -0,0 +1,48 @@
+use crate::game::{GameData, GameLobbyEvent};
+
+use super::ServerRole;
+use anyhow::Error;
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+use werewolf_rs::{
+    game::{CauseOfDeath, GameConfig, Role, RoleData},
+    util::PlayerId,
+};
+
+pub struct VillageIdiot;
+
+#[async_trait]
+impl ServerRole for VillageIdiot {
+    async fn run_night_turn(
+        &self,
+        _: &mpsc::Sender<GameLobbyEvent>,
+        _: &GameConfig,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /*
+    The village spares the idiot the first time it lynches them, but they lose their vote
+    */
+    fn intercept_death(
+        &self,
+        game_data: &mut GameData,
+        player_id: PlayerId,
+        cause: &CauseOfDeath,
+    ) -> bool {
+        if !matches!(cause, CauseOfDeath::VillageVote) || !game_data.has_powers(&Role::VillageIdiot)
+        {
+            return false;
+        }
+        match game_data.players.get_mut(&player_id) {
+            Some(player) => match &mut player.role_data {
+                RoleData::VillageIdiot { revealed } if !*revealed => {
+                    *revealed = true;
+                    true
+                }
+                _ => false,
+            },
+            None => false,
+        }
+    }
+}
@@ -1,15 +1,21 @@
 use std::collections::HashMap;
 
-use crate::game::{client_manager::ClientEvent, GameLobby, Player};
+use crate::game::{
+    client_manager::{ClientEvent, InteractionEvent},
+    deadline::Deadline,
+    game_runner::GameRunner,
+    GameLobby, Player,
+};
 
 use super::ServerRole;
 use anyhow::Error;
 use async_trait::async_trait;
 use futures::stream::{FuturesUnordered, StreamExt};
+use rand::{prelude::SliceRandom, Rng};
 use tokio::sync::{mpsc, oneshot};
 use werewolf_rs::{
-    game::{CauseOfDeath, Role},
-    packet::{InteractionFollowup, InteractionRequest, InteractionResponse},
+    game::{CauseOfDeath, Faction, GameConfig, Role, WerewolfVoteFallback},
+    packet::{InteractionFollowup, InteractionRequest, InteractionResponse, WvResolution},
     util::{InteractionId, PlayerId},
 };
 
@@ -20,50 +26,138 @@ impl ServerRole for Werewolf {
     async fn run_night_turn(
         &self,
         lobby_sender: &tokio::sync::mpsc::Sender<crate::game::GameLobbyEvent>,
+        game_config: &GameConfig,
     ) -> Result<(), anyhow::Error> {
         enum VotingStatus {
             NotParticipating,
+            //The little girl sees the votes, but not who cast them
+            Observing,
+            //The werewolf is disconnected and does not take part in the vote until it reconnects
+            Absent,
             NoVote,
             VotingFor(PlayerId),
             LockedVote(PlayerId),
         }
         type ClientMap = HashMap<PlayerId, (mpsc::Sender<ClientEvent>, VotingStatus)>;
 
+        //Every member of the pack takes part in the vote, including the special wolves
         fn participating(player: &Player) -> bool {
-            player.role_data.get_role() == Role::Werewolf
+            player.is_alive && player.faction == Some(Faction::Werewolves)
+        }
+        fn is_voting(status: &VotingStatus) -> bool {
+            matches!(
+                status,
+                VotingStatus::NoVote | VotingStatus::VotingFor(_) | VotingStatus::LockedVote(_)
+            )
+        }
+        /*
+        Returns the vote of one of the werewolves if all werewolves that are still voting have locked their vote
+        */
+        fn all_locked(clients: &ClientMap) -> Option<PlayerId> {
+            let mut locked_vote = None;
+            for (_, status) in clients.values().filter(|(_, status)| is_voting(status)) {
+                match status {
+                    VotingStatus::LockedVote(vote) => locked_vote = Some(*vote),
+                    _ => return None,
+                }
+            }
+            locked_vote
+        }
+        /*
+        Returns the vote of the werewolves if all werewolves that are still voting vote for the same player (locked or not)
+        */
+        fn unanimous_vote(clients: &ClientMap) -> Option<PlayerId> {
+            let mut unanimous_vote = None;
+            for (_, status) in clients.values().filter(|(_, status)| is_voting(status)) {
+                match (status, unanimous_vote) {
+                    (VotingStatus::VotingFor(vote) | VotingStatus::LockedVote(vote), None) => {
+                        unanimous_vote = Some(*vote)
+                    }
+                    (
+                        VotingStatus::VotingFor(vote) | VotingStatus::LockedVote(vote),
+                        Some(other),
+                    ) if *vote == other => {}
+                    _ => return None,
+                }
+            }
+            unanimous_vote
+        }
+        /*
+        Chooses the victim once the time for the vote is up, based on the current (locked or unlocked) votes
+        */
+        fn resolve_after_deadline(
+            clients: &ClientMap,
+            fallback: &WerewolfVoteFallback,
+        ) -> (Option<PlayerId>, WvResolution) {
+            if let Some(vote) = unanimous_vote(clients) {
+                return (Some(vote), WvResolution::Agreed);
+            }
+            let votes: Vec<(PlayerId, PlayerId)> = clients
+                .iter()
+                .filter_map(|(id, (_, status))| match status {
+                    VotingStatus::VotingFor(vote) | VotingStatus::LockedVote(vote) => {
+                        Some((*id, *vote))
+                    }
+                    _ => None,
+                })
+                .collect();
+            let most_voted = GameRunner::most_voted(&votes, &HashMap::new());
+            match fallback {
+                WerewolfVoteFallback::Majority => {
+                    let vote = match most_voted[..] {
+                        [vote] => Some(vote),
+                        _ => None,
+                    };
+                    (vote, WvResolution::Majority)
+                }
+                WerewolfVoteFallback::RandomAmongTop => (
+                    most_voted.choose(&mut rand::thread_rng()).copied(),
+                    WvResolution::Random,
+                ),
+                WerewolfVoteFallback::NoKill => (None, WvResolution::NoKill),
+            }
         }
+        //The pack can't choose one of its own members as its victim
         fn selectable(player: &Player) -> bool {
-            player.is_alive
+            player.is_alive && !participating(player)
         }
 
-        //Get a list of participating and selectable players
-        let (mut clients, selectable): (ClientMap, Vec<PlayerId>) =
+        //Get a list of participating, selectable and living players
+        let (mut clients, selectable, alive): (ClientMap, Vec<PlayerId>, Vec<PlayerId>) =
             GameLobby::access_game_data(lobby_sender, move |game_data, clients| {
                 let mut ret_clients: ClientMap = HashMap::new();
                 let mut ret_selectable: Vec<PlayerId> = Vec::new();
+                let mut ret_alive: Vec<PlayerId> = Vec::new();
                 for (id, player) in game_data.players.iter() {
-                    if participating(player) {
-                        ret_clients.insert(
-                            *id,
-                            (clients.get(id).unwrap().clone(), VotingStatus::NoVote),
-                        );
-                    } else if player.role_data.get_role() == Role::Spectator {
-                        ret_clients.insert(
-                            *id,
-                            (
-                                clients.get(id).unwrap().clone(),
-                                VotingStatus::NotParticipating,
-                            ),
-                        );
-                    }
                     if selectable(player) {
                         ret_selectable.push(*id);
                     }
+                    if player.is_alive {
+                        ret_alive.push(*id);
+                    }
+                    let sender = match clients.get(id) {
+                        Some(sender) => sender.clone(),
+                        None => {
+                            warn!("Player without a client manager during werewolf vote");
+                            continue;
+                        }
+                    };
+                    if participating(player) {
+                        ret_clients.insert(*id, (sender, VotingStatus::NoVote));
+                    } else if player.role_data.get_role() == Role::Spectator {
+                        ret_clients.insert(*id, (sender, VotingStatus::NotParticipating));
+                    } else if player.is_alive
+                        && player.role_data.get_role() == Role::LittleGirl
+                        && game_data.has_powers(&Role::LittleGirl)
+                    {
+                        ret_clients.insert(*id, (sender, VotingStatus::Observing));
+                    }
                 }
-                (ret_clients, ret_selectable)
+                (ret_clients, ret_selectable, ret_alive)
             })
             .await?;
 
+        let deadline = Deadline::from_seconds(game_config.durations.werewolf_vote);
         let (interaction_send, mut interaction_receive) = mpsc::channel(8);
         //Create the interactions
         let mut id_futs = FuturesUnordered::new();
@@ -72,8 +166,13 @@ impl ServerRole for Werewolf {
             sender
                 .send(ClientEvent::CreateInteraction(
                     InteractionRequest::WvBegin {
-                        selectable_players: selectable.clone(),
+                        //The little girl would recognize the werewolves as the players that can't be selected
+                        selectable_players: match status {
+                            VotingStatus::Observing => alive.clone(),
+                            _ => selectable.clone(),
+                        },
                         can_vote: matches!(status, VotingStatus::NoVote),
+                        deadline: Deadline::timestamp(&deadline),
                     },
                     interaction_send.clone(),
                     id_send,
@@ -93,22 +192,101 @@ impl ServerRole for Werewolf {
             clients: &ClientMap,
             interaction_ids: &HashMap<PlayerId, InteractionId>,
         ) -> Result<(), Error> {
-            for (id, (sender, _)) in clients.iter() {
+            for (id, (sender, status)) in clients.iter() {
                 let interaction_id = interaction_ids.get(id).unwrap();
+                let update = match (status, update) {
+                    (VotingStatus::Observing, InteractionFollowup::WvNewVote { vote, .. }) => {
+                        InteractionFollowup::WvNewVote {
+                            vote: *vote,
+                            voted_by: None,
+                        }
+                    }
+                    (VotingStatus::Observing, InteractionFollowup::WvLockedVote { vote, .. }) => {
+                        InteractionFollowup::WvLockedVote {
+                            vote: *vote,
+                            voted_by: None,
+                        }
+                    }
+                    //Absent werewolves would give away who is a werewolf
+                    (
+                        VotingStatus::Observing,
+                        InteractionFollowup::PlayerAbsent { .. }
+                        | InteractionFollowup::PlayerReturned { .. },
+                    ) => continue,
+                    _ => (*update).clone(),
+                };
                 sender
-                    .send(ClientEvent::FollowupInteraction(
-                        *interaction_id,
-                        (*update).clone(),
-                    ))
+                    .send(ClientEvent::FollowupInteraction(*interaction_id, update))
                     .await?;
             }
             Ok(())
         }
 
-        let mut final_vote: Option<PlayerId> = None;
-        //Main voting event loop
-        while let Some((player_id, response)) = interaction_receive.recv().await {
+        //The little girl may be caught spying, in which case the werewolves don't need to vote
+        let caught = clients
+            .iter()
+            .filter(|(_, (_, status))| matches!(status, VotingStatus::Observing))
+            .map(|(id, _)| *id)
+            .find(|_| rand::thread_rng().gen_bool(game_config.little_girl_catch_chance));
+        let mut final_vote: Option<PlayerId> = caught;
+        //If no werewolf is left to vote, there is no victim
+        let mut resolution = match caught {
+            Some(_) => WvResolution::LittleGirlCaught,
+            None => WvResolution::NoKill,
+        };
+        //Main voting event loop. The vote ends without a victim if no werewolf is connected anymore
+        while caught.is_none() && clients.values().any(|(_, status)| is_voting(status)) {
+            let (player_id, event) = match Deadline::recv(&deadline, &mut interaction_receive).await
+            {
+                Some(event) => event,
+                None => {
+                    //When the time is up, the configured fallback decides unless the werewolves agree already
+                    let (vote, fallback_resolution) =
+                        resolve_after_deadline(&clients, &game_config.werewolf_vote_fallback);
+                    final_vote = vote;
+                    resolution = fallback_resolution;
+                    break;
+                }
+            };
+            let response = match event {
+                InteractionEvent::Response(response) => response,
+                InteractionEvent::Disconnected => {
+                    let (_, status) = clients.get_mut(&player_id).unwrap();
+                    if is_voting(status) {
+                        *status = VotingStatus::Absent;
+                        send_update(
+                            &InteractionFollowup::PlayerAbsent { player: player_id },
+                            &clients,
+                            &interaction_ids,
+                        )
+                        .await?;
+                        //The remaining werewolves may have already agreed on their victim
+                        if let Some(vote) = all_locked(&clients) {
+                            final_vote = Some(vote);
+                            resolution = WvResolution::Agreed;
+                            break;
+                        }
+                    }
+                    continue;
+                }
+                InteractionEvent::Reconnected => {
+                    let (_, status) = clients.get_mut(&player_id).unwrap();
+                    if let VotingStatus::Absent = status {
+                        *status = VotingStatus::NoVote;
+                        send_update(
+                            &InteractionFollowup::PlayerReturned { player: player_id },
+                            &clients,
+                            &interaction_ids,
+                        )
+                        .await?;
+                    }
+                    continue;
+                }
+            };
             match response {
+                InteractionResponse::WvVote { vote } if !selectable.contains(&vote) => {
+                    warn!("Received werewolf vote for a player that can't be selected");
+                }
                 InteractionResponse::WvVote { vote } => {
                     let (_, status) = clients.get_mut(&player_id).unwrap();
                     match status {
@@ -117,7 +295,7 @@ impl ServerRole for Werewolf {
                             send_update(
                                 &InteractionFollowup::WvNewVote {
                                     vote,
-                                    voted_by: player_id,
+                                    voted_by: Some(player_id),
                                 },
                                 &clients,
                                 &interaction_ids,
@@ -131,29 +309,9 @@ impl ServerRole for Werewolf {
                 }
                 InteractionResponse::WvLockVote => {
                     //Locking the vote is only allowed if all werewolves vote for the same player
-                    let mut lock_allowed = true;
-                    let mut voting_statuses = clients.values().map(|(_, status)| status);
-                    if let VotingStatus::VotingFor(first_vote)
-                    | VotingStatus::LockedVote(first_vote) = *voting_statuses.next().unwrap()
-                    {
-                        for status in voting_statuses {
-                            if let VotingStatus::VotingFor(vote) | VotingStatus::LockedVote(vote) =
-                                *status
-                            {
-                                if vote != first_vote {
-                                    lock_allowed = false;
-                                    break;
-                                }
-                            } else {
-                                lock_allowed = false;
-                                break;
-                            }
-                        }
-                    } else {
-                        lock_allowed = false;
-                    }
-                    if !lock_allowed {
+                    if unanimous_vote(&clients).is_none() {
                         warn!("Received WvLockVote packet while not all werewolves have the same vote");
+                        continue;
                     }
 
                     let (_, status) = clients.get_mut(&player_id).unwrap();
@@ -163,18 +321,16 @@ impl ServerRole for Werewolf {
                         send_update(
                             &InteractionFollowup::WvLockedVote {
                                 vote,
-                                voted_by: player_id,
+                                voted_by: Some(player_id),
                             },
                             &clients,
                             &interaction_ids,
                         )
                         .await?;
-                        if clients
-                            .values()
-                            .all(|(_, status)| matches!(status, VotingStatus::LockedVote(_)))
-                        {
+                        if all_locked(&clients).is_some() {
                             //The vote ends when all werewolves locked their vote
                             final_vote = Some(vote);
+                            resolution = WvResolution::Agreed;
                             break;
                         }
                     } else {
@@ -189,11 +345,19 @@ impl ServerRole for Werewolf {
 
         //Broadcast and apply the vote result
         send_update(
-            &InteractionFollowup::WvVoteFinished { vote: final_vote },
+            &InteractionFollowup::WvVoteFinished {
+                vote: final_vote,
+                resolution,
+            },
             &clients,
             &interaction_ids,
         )
         .await?;
+        for (id, (sender, _)) in clients.iter() {
+            sender
+                .send(ClientEvent::CloseInteraction(interaction_ids[id]))
+                .await?;
+        }
         if let Some(vote) = final_vote {
             GameLobby::access_game_data(lobby_sender, move |game_data, _| {
                 game_data
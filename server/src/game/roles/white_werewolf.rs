@@ -0,0 +1,80 @@
+use crate::game::{
+    deadline::Deadline, interaction::select_target, GameData, GameLobby, GameLobbyEvent,
+};
+
+use super::{last_alive, targets_by_player, ServerRole};
+use anyhow::Error;
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use tokio::sync::mpsc;
+use werewolf_rs::{
+    game::{CauseOfDeath, Faction, GameConfig, Role, RoleData},
+    packet::TargetPurpose,
+    util::PlayerId,
+};
+
+pub struct WhiteWerewolf;
+
+#[async_trait]
+impl ServerRole for WhiteWerewolf {
+    /*
+    Every second night, the white werewolf may secretly kill another member of the pack
+    */
+    async fn run_night_turn(
+        &self,
+        lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+        game_config: &GameConfig,
+    ) -> Result<(), Error> {
+        let white_werewolves: Vec<(PlayerId, Vec<PlayerId>)> =
+            GameLobby::access_game_data(lobby_sender, |game_data, _| {
+                let pack: Vec<PlayerId> = game_data
+                    .players
+                    .iter()
+                    .filter(|(_, player)| {
+                        player.is_alive && player.faction == Some(Faction::Werewolves)
+                    })
+                    .map(|(id, _)| *id)
+                    .collect();
+                let is_white_werewolf =
+                    |role_data: &RoleData| role_data.get_role() == Role::WhiteWerewolf;
+                targets_by_player(game_data, is_white_werewolf, &pack)
+            })
+            .await?;
+
+        let deadline = Deadline::from_seconds(game_config.durations.night_action);
+        try_join_all(
+            white_werewolves
+                .into_iter()
+                .filter(|(_, pack)| !pack.is_empty())
+                .map(|(white_werewolf, pack)| async move {
+                    let (interaction, target) = select_target(
+                        lobby_sender,
+                        white_werewolf,
+                        TargetPurpose::WhiteWerewolfKill,
+                        pack,
+                        deadline,
+                    )
+                    .await?;
+                    interaction.close().await?;
+                    if let Some(target) = target {
+                        GameLobby::access_game_data(lobby_sender, move |game_data, _| {
+                            game_data
+                                .dying_players
+                                .push((target, CauseOfDeath::Werewolves));
+                        })
+                        .await?;
+                    }
+                    Ok::<(), Error>(())
+                }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /*
+    The white werewolf wins if they are the last one alive
+    */
+    fn has_won(&self, game_data: &GameData, player_id: PlayerId) -> bool {
+        last_alive(game_data, player_id)
+    }
+}
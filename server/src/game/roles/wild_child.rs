@@ -0,0 +1,84 @@
+use crate::game::{
+    deadline::Deadline, interaction::select_target, GameData, GameLobby, GameLobbyEvent,
+};
+
+use super::{living_players, targets_by_player, ServerRole};
+use anyhow::Error;
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use tokio::sync::mpsc;
+use werewolf_rs::{
+    game::{Faction, GameConfig, Role, RoleData},
+    packet::TargetPurpose,
+    util::PlayerId,
+};
+
+pub struct WildChild;
+
+#[async_trait]
+impl ServerRole for WildChild {
+    /*
+    In the first night, the wild child chooses another player as their role model
+    */
+    async fn run_night_turn(
+        &self,
+        lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+        game_config: &GameConfig,
+    ) -> Result<(), Error> {
+        let wild_children: Vec<(PlayerId, Vec<PlayerId>)> =
+            GameLobby::access_game_data(lobby_sender, |game_data, _| {
+                let is_wild_child = |role_data: &RoleData| role_data.get_role() == Role::WildChild;
+                targets_by_player(game_data, is_wild_child, &living_players(game_data))
+            })
+            .await?;
+
+        let deadline = Deadline::from_seconds(game_config.durations.night_action);
+        try_join_all(
+            wild_children
+                .into_iter()
+                .map(|(wild_child, selectable)| async move {
+                    let (interaction, target) = select_target(
+                        lobby_sender,
+                        wild_child,
+                        TargetPurpose::WildChildRoleModel,
+                        selectable,
+                        deadline,
+                    )
+                    .await?;
+                    interaction.close().await?;
+                    GameLobby::access_game_data(lobby_sender, move |game_data, _| {
+                        if let Some(RoleData::WildChild { role_model, .. }) = game_data
+                            .players
+                            .get_mut(&wild_child)
+                            .map(|player| &mut player.role_data)
+                        {
+                            *role_model = target;
+                        }
+                    })
+                    .await?;
+                    lobby_sender.send(GameLobbyEvent::SendUpdate).await?;
+                    Ok::<(), Error>(())
+                }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /*
+    The wild child becomes a werewolf once their role model has died
+    */
+    fn on_other_death(&self, game_data: &mut GameData, player_id: PlayerId, dead_player: PlayerId) {
+        if let Some(player) = game_data.players.get_mut(&player_id) {
+            if let RoleData::WildChild {
+                role_model: Some(role_model),
+                transformed,
+            } = &mut player.role_data
+            {
+                if *role_model == dead_player && !*transformed {
+                    *transformed = true;
+                    player.faction = Some(Faction::Werewolves);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use crate::game::{deadline::Deadline, interaction::select_target, GameLobby, GameLobbyEvent};
+
+use super::{living_players, ServerRole};
+use anyhow::Error;
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use tokio::sync::mpsc;
+use werewolf_rs::{
+    game::{CauseOfDeath, GameConfig, RoleData},
+    packet::TargetPurpose,
+    util::PlayerId,
+};
+
+pub struct Witch;
+
+/*
+What a witch can do in the current night
+*/
+struct WitchTurn {
+    witch: PlayerId,
+    //The players that are going to die this night, if the healing potion is still available
+    healable: Option<Vec<PlayerId>>,
+    //The players that can be poisoned, if the poison potion is still available
+    poisonable: Option<Vec<PlayerId>>,
+}
+
+#[async_trait]
+impl ServerRole for Witch {
+    async fn run_night_turn(
+        &self,
+        lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+        game_config: &GameConfig,
+    ) -> Result<(), Error> {
+        let turns: Vec<WitchTurn> = GameLobby::access_game_data(lobby_sender, |game_data, _| {
+            let dying: Vec<PlayerId> = game_data.dying_players.iter().map(|(id, _)| *id).collect();
+            //Players that die anyway can't be poisoned
+            let alive: Vec<PlayerId> = living_players(game_data)
+                .into_iter()
+                .filter(|id| !dying.contains(id))
+                .collect();
+            game_data
+                .players
+                .iter()
+                .filter(|(_, player)| player.is_alive)
+                .filter_map(|(id, player)| match player.role_data {
+                    RoleData::Witch {
+                        healing_potion_used,
+                        poison_potion_used,
+                    } => Some(WitchTurn {
+                        witch: *id,
+                        healable: (!healing_potion_used && !dying.is_empty())
+                            .then(|| dying.clone()),
+                        poisonable: (!poison_potion_used)
+                            .then(|| alive.iter().copied().filter(|p| p != id).collect()),
+                    }),
+                    _ => None,
+                })
+                .collect()
+        })
+        .await?;
+
+        let deadline = Deadline::from_seconds(game_config.durations.night_action);
+        try_join_all(
+            turns
+                .into_iter()
+                .map(|turn| use_potions(lobby_sender, turn, deadline)),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/*
+Lets a witch decide whether to heal one of the dying players and whether to poison another player
+*/
+async fn use_potions(
+    lobby_sender: &mpsc::Sender<GameLobbyEvent>,
+    turn: WitchTurn,
+    deadline: Option<Deadline>,
+) -> Result<(), Error> {
+    let witch = turn.witch;
+    if let Some(healable) = turn.healable {
+        let (interaction, target) = select_target(
+            lobby_sender,
+            witch,
+            TargetPurpose::WitchHeal,
+            healable,
+            deadline,
+        )
+        .await?;
+        interaction.close().await?;
+        if let Some(target) = target {
+            GameLobby::access_game_data(lobby_sender, move |game_data, _| {
+                game_data.dying_players.retain(|(id, _)| *id != target);
+                if let Some(RoleData::Witch {
+                    healing_potion_used,
+                    ..
+                }) = game_data
+                    .players
+                    .get_mut(&witch)
+                    .map(|player| &mut player.role_data)
+                {
+                    *healing_potion_used = true;
+                }
+            })
+            .await?;
+        }
+    }
+    if let Some(poisonable) = turn.poisonable {
+        let (interaction, target) = select_target(
+            lobby_sender,
+            witch,
+            TargetPurpose::WitchPoison,
+            poisonable,
+            deadline,
+        )
+        .await?;
+        interaction.close().await?;
+        if let Some(target) = target {
+            GameLobby::access_game_data(lobby_sender, move |game_data, _| {
+                game_data.dying_players.push((target, CauseOfDeath::Poison));
+                if let Some(RoleData::Witch {
+                    poison_potion_used, ..
+                }) = game_data
+                    .players
+                    .get_mut(&witch)
+                    .map(|player| &mut player.role_data)
+                {
+                    *poison_potion_used = true;
+                }
+            })
+            .await?;
+        }
+    }
+    Ok(())
+}
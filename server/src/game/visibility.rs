@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+
+use werewolf_rs::{
+    game::{Faction, GameConfig, GameInfo, LobbyPhase, PlayerInfo, Role, RoleData, RoleInfo},
+    util::PlayerId,
+};
+
+use super::{GameData, Player};
+
+/*
+Builds the information on the game that is visible to a specific player
+*/
+pub fn game_info(
+    viewer_id: PlayerId,
+    game_data: &GameData,
+    config: GameConfig,
+    phase: LobbyPhase,
+) -> GameInfo {
+    let viewer = game_data.players.get(&viewer_id);
+    let players = game_data
+        .players
+        .iter()
+        .map(|(id, player)| {
+            let role_info = if *id == viewer_id {
+                RoleInfo::KnownData(player.role_data.clone())
+            } else {
+                role_info(viewer, *id, player, &config, phase)
+            };
+            (
+                *id,
+                PlayerInfo {
+                    name: player.name.clone(),
+                    role_info,
+                    is_alive: player.is_alive,
+                    is_lobby_host: player.is_lobby_host,
+                    is_connected: player.is_connected,
+                    lover: lover_info(viewer_id, viewer, player, phase),
+                    is_mayor: player.status.is_mayor,
+                },
+            )
+        })
+        .collect::<HashMap<_, _>>();
+    GameInfo {
+        players,
+        seating: game_data.seating.clone(),
+        config,
+        phase,
+    }
+}
+
+/*
+The visibility policy: What a viewer knows about the role of another player
+*/
+fn role_info(
+    viewer: Option<&Player>,
+    target_id: PlayerId,
+    target: &Player,
+    config: &GameConfig,
+    phase: LobbyPhase,
+) -> RoleInfo {
+    //Everyone knows who is only watching, and all roles are revealed once the game is over
+    if let RoleData::Spectator = target.role_data {
+        return RoleInfo::KnownData(target.role_data.clone());
+    }
+    if phase == LobbyPhase::PostGame {
+        return RoleInfo::KnownData(target.role_data.clone());
+    }
+
+    //A village idiot that has survived the lynch is known to everyone
+    if let RoleData::VillageIdiot { revealed: true } = target.role_data {
+        return RoleInfo::KnownData(target.role_data.clone());
+    }
+
+    let target_role = target.role_data.get_role();
+    if !target.is_alive {
+        if let Some(role) = revealed_on_death(&target_role, config) {
+            return RoleInfo::Known(role);
+        }
+    }
+    if let Some(viewer) = viewer {
+        //Werewolves know their pack, but a member that plays for themselves looks like any other werewolf
+        if viewer.faction == Some(Faction::Werewolves)
+            && target.faction == Some(Faction::Werewolves)
+        {
+            if target_role.wins_alone() {
+                return RoleInfo::Known(Role::Werewolf);
+            }
+            return RoleInfo::Known(target_role);
+        }
+        //Siblings know each other from the start
+        let viewer_role = viewer.role_data.get_role();
+        if matches!(viewer_role, Role::Sister | Role::Brother) && viewer_role == target_role {
+            return RoleInfo::Known(target_role);
+        }
+        //Information the viewer has learned during the game, for example as the seer
+        if let Some(role_info) = viewer.known_roles.get(&target_id) {
+            return role_info.clone();
+        }
+    }
+    RoleInfo::Unknown
+}
+
+/*
+The role of a player that has just died, as it is announced to everyone
+*/
+pub fn revealed_on_death(role: &Role, config: &GameConfig) -> Option<Role> {
+    if config.reveal_dead_roles {
+        Some(role.clone())
+    } else {
+        None
+    }
+}
+
+/*
+Whether a viewer knows who the target is in love with
+*/
+fn lover_info(
+    viewer_id: PlayerId,
+    viewer: Option<&Player>,
+    target: &Player,
+    phase: LobbyPhase,
+) -> Option<PlayerId> {
+    let lover = target.status.lover?;
+    let viewer = viewer?;
+    //The lovers know each other, and cupid knows whom they have linked
+    if phase == LobbyPhase::PostGame
+        || lover == viewer_id
+        || viewer.status.lover == Some(lover)
+        || viewer.role_data.get_role() == Role::Cupid
+    {
+        Some(lover)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn role_seen_by(
+        viewer: u64,
+        target: u64,
+        game_data: &GameData,
+        config: GameConfig,
+    ) -> RoleInfo {
+        let game_info = game_info(PlayerId::new(viewer), game_data, config, LobbyPhase::InGame);
+        game_info.players[&PlayerId::new(target)].role_info.clone()
+    }
+
+    #[test]
+    fn dead_roles_are_only_revealed_if_configured() {
+        let mut game_data = GameData::with_roles(&[Role::Villager, Role::Seer]);
+        game_data
+            .players
+            .get_mut(&PlayerId::new(1))
+            .unwrap()
+            .is_alive = false;
+        let revealed = role_seen_by(0, 1, &game_data, GameConfig::default());
+        assert!(matches!(revealed, RoleInfo::Known(Role::Seer)));
+        let config = GameConfig {
+            reveal_dead_roles: false,
+            ..GameConfig::default()
+        };
+        let hidden = role_seen_by(0, 1, &game_data, config);
+        assert!(matches!(hidden, RoleInfo::Unknown));
+    }
+
+    #[test]
+    fn werewolves_know_their_pack() {
+        let game_data = GameData::with_roles(&[Role::Werewolf, Role::Werewolf, Role::Villager]);
+        let packmate = role_seen_by(0, 1, &game_data, GameConfig::default());
+        assert!(matches!(packmate, RoleInfo::Known(Role::Werewolf)));
+        let werewolf = role_seen_by(2, 0, &game_data, GameConfig::default());
+        assert!(matches!(werewolf, RoleInfo::Unknown));
+    }
+
+    #[test]
+    fn pack_doesnt_recognize_the_white_werewolf() {
+        let game_data = GameData::with_roles(&[Role::Werewolf, Role::WhiteWerewolf]);
+        let white_werewolf = role_seen_by(0, 1, &game_data, GameConfig::default());
+        assert!(matches!(white_werewolf, RoleInfo::Known(Role::Werewolf)));
+        let own_role = role_seen_by(1, 1, &game_data, GameConfig::default());
+        assert!(matches!(
+            own_role,
+            RoleInfo::KnownData(RoleData::WhiteWerewolf)
+        ));
+    }
+}
@@ -0,0 +1,191 @@
+use werewolf_rs::{
+    game::{Faction, Role},
+    util::PlayerId,
+};
+
+use super::{roles::ServerRoleDelegator, GameData};
+
+/*
+Checks whether one of the factions has won the game.
+Returns the winning faction together with all of its members (dead or alive)
+*/
+pub fn check_winner(game_data: &GameData) -> Option<(Faction, Vec<PlayerId>)> {
+    //Lovers from different factions form their own team and win if they are the last ones alive
+    let mixed_lovers = mixed_lovers(game_data);
+    let alive_players: Vec<PlayerId> = game_data
+        .players
+        .iter()
+        .filter(|(_, player)| player.is_alive)
+        .map(|(id, _)| *id)
+        .collect();
+    if let Some(lovers) = &mixed_lovers {
+        if !alive_players.is_empty() && alive_players.iter().all(|id| lovers.contains(id)) {
+            return Some((Faction::Lovers, lovers.to_vec()));
+        }
+    }
+    //A single player that has won alone ends the game for everyone
+    for (id, player) in game_data.players.iter() {
+        if player.role_data.get_role().has_won(game_data, *id) {
+            return Some((Faction::Solo, vec![*id]));
+        }
+    }
+
+    let mut alive_werewolves = 0;
+    let mut alive_loners = 0;
+    let mut alive_killers = 0;
+    let mut alive_others = 0;
+    for player in game_data.players.values().filter(|p| p.is_alive) {
+        match (player.faction, player.role_data.get_role()) {
+            (Some(Faction::Werewolves), role) => {
+                alive_werewolves += 1;
+                if role.wins_alone() {
+                    alive_loners += 1;
+                }
+            }
+            (_, Role::SerialKiller) => alive_killers += 1,
+            (Some(_), _) => alive_others += 1,
+            (None, _) => {}
+        }
+    }
+
+    //Neither the village nor the werewolves can win while the serial killer is alive
+    let winner = if alive_killers > 0 {
+        return None;
+    } else if alive_werewolves == 0 {
+        Faction::Village
+    } else if alive_werewolves >= alive_others && alive_loners == 0 {
+        //Once the werewolves reach parity, the village can no longer outvote them.
+        //The pack hasn't won yet while a member that plays for themselves is still alive
+        Faction::Werewolves
+    } else {
+        return None;
+    };
+    let winning_players = game_data
+        .players
+        .iter()
+        .filter(|(_, player)| {
+            player.faction == Some(winner) && !player.role_data.get_role().wins_alone()
+        })
+        .filter(|(id, _)| !matches!(mixed_lovers, Some(lovers) if lovers.contains(id)))
+        .map(|(id, _)| *id)
+        .collect();
+    Some((winner, winning_players))
+}
+
+/*
+Returns the two lovers if they play for different factions
+*/
+fn mixed_lovers(game_data: &GameData) -> Option<[PlayerId; 2]> {
+    game_data.players.iter().find_map(|(id, player)| {
+        let lover_id = player.status.lover?;
+        let lover = game_data.players.get(&lover_id)?;
+        (player.faction != lover.faction).then_some([*id, lover_id])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use werewolf_rs::game::CauseOfDeath;
+
+    use super::*;
+
+    fn kill(game_data: &mut GameData, id: u64) {
+        game_data
+            .players
+            .get_mut(&PlayerId::new(id))
+            .unwrap()
+            .is_alive = false;
+    }
+
+    #[test]
+    fn no_winner_while_both_factions_can_win() {
+        let game_data = GameData::with_roles(&[Role::Werewolf, Role::Villager, Role::Villager]);
+        assert_eq!(check_winner(&game_data), None);
+    }
+
+    #[test]
+    fn village_wins_without_werewolves() {
+        let mut game_data = GameData::with_roles(&[Role::Werewolf, Role::Villager, Role::Villager]);
+        kill(&mut game_data, 0);
+        kill(&mut game_data, 1);
+        let (winner, winning_players) = check_winner(&game_data).unwrap();
+        assert_eq!(winner, Faction::Village);
+        assert_eq!(
+            winning_players.into_iter().collect::<HashSet<_>>(),
+            vec![PlayerId::new(1), PlayerId::new(2)]
+                .into_iter()
+                .collect()
+        );
+    }
+
+    #[test]
+    fn werewolves_win_at_parity() {
+        let mut game_data = GameData::with_roles(&[Role::Werewolf, Role::Villager, Role::Villager]);
+        kill(&mut game_data, 2);
+        assert_eq!(
+            check_winner(&game_data),
+            Some((Faction::Werewolves, vec![PlayerId::new(0)]))
+        );
+    }
+
+    #[test]
+    fn no_pack_win_while_the_white_werewolf_is_alive() {
+        let mut game_data = GameData::with_roles(&[
+            Role::Werewolf,
+            Role::WhiteWerewolf,
+            Role::Villager,
+            Role::Villager,
+        ]);
+        kill(&mut game_data, 3);
+        assert_eq!(check_winner(&game_data), None);
+        kill(&mut game_data, 1);
+        assert_eq!(
+            check_winner(&game_data),
+            Some((Faction::Werewolves, vec![PlayerId::new(0)]))
+        );
+    }
+
+    #[test]
+    fn lovers_from_different_factions_win_together() {
+        let mut game_data = GameData::with_roles(&[Role::Werewolf, Role::Villager, Role::Seer]);
+        let lovers = [PlayerId::new(0), PlayerId::new(1)];
+        for (lover, other) in lovers.iter().zip(lovers.iter().rev()) {
+            game_data.players.get_mut(lover).unwrap().status.lover = Some(*other);
+        }
+        assert_eq!(check_winner(&game_data), None);
+        kill(&mut game_data, 2);
+        let (winner, winning_players) = check_winner(&game_data).unwrap();
+        assert_eq!(winner, Faction::Lovers);
+        assert_eq!(
+            winning_players.into_iter().collect::<HashSet<_>>(),
+            lovers.iter().copied().collect()
+        );
+    }
+
+    #[test]
+    fn serial_killer_blocks_other_wins() {
+        let mut game_data =
+            GameData::with_roles(&[Role::Werewolf, Role::SerialKiller, Role::Villager]);
+        kill(&mut game_data, 0);
+        assert_eq!(check_winner(&game_data), None);
+        kill(&mut game_data, 2);
+        assert_eq!(
+            check_winner(&game_data),
+            Some((Faction::Solo, vec![PlayerId::new(1)]))
+        );
+    }
+
+    #[test]
+    fn lynched_tanner_wins_alone() {
+        let mut game_data = GameData::with_roles(&[Role::Werewolf, Role::Tanner, Role::Villager]);
+        let tanner = game_data.players.get_mut(&PlayerId::new(1)).unwrap();
+        tanner.is_alive = false;
+        tanner.status.cause_of_death = Some(CauseOfDeath::VillageVote);
+        assert_eq!(
+            check_winner(&game_data),
+            Some((Faction::Solo, vec![PlayerId::new(1)]))
+        );
+    }
+}
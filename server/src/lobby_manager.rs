@@ -1,21 +1,29 @@
 use crate::{
     game::{GameLobby, GameLobbyEvent},
-    util::{generate_id, WsReceiver, WsSender},
+    util::{generate_id, send_logging, WsReceiver, WsSender},
 };
 use std::collections::HashMap;
 use std::fmt::Debug;
 use tokio::sync::mpsc;
-use werewolf_rs::util::LobbyId;
+use werewolf_rs::{packet::PacketToClient, util::LobbyId};
 
 pub enum LobbyManagerEvent {
     CreateNewLobby {
         ws_read: WsReceiver,
         ws_write: WsSender,
+        name: String,
     },
     JoinLobby {
         ws_read: WsReceiver,
         ws_write: WsSender,
         lobby_id: LobbyId,
+        name: String,
+    },
+    Reconnect {
+        ws_read: WsReceiver,
+        ws_write: WsSender,
+        lobby_id: LobbyId,
+        token: String,
     },
 }
 
@@ -25,6 +33,7 @@ impl Debug for LobbyManagerEvent {
             Self::CreateNewLobby {
                 ws_read: _,
                 ws_write: _,
+                name: _,
             } => {
                 write!(f, "LobbyEvent::CreateNewLobby")
             }
@@ -32,6 +41,7 @@ impl Debug for LobbyManagerEvent {
                 ws_read: _,
                 ws_write: _,
                 lobby_id,
+                name: _,
             } => {
                 write!(
                     f,
@@ -39,6 +49,14 @@ impl Debug for LobbyManagerEvent {
                     lobby_id
                 )
             }
+            Self::Reconnect {
+                ws_read: _,
+                ws_write: _,
+                lobby_id,
+                token: _,
+            } => {
+                write!(f, "LobbyEvent::Reconnect{{ lobby_id: {:?} }}", lobby_id)
+            }
         }
     }
 }
@@ -68,17 +86,48 @@ impl LobbyManager {
                     ws_read,
                     ws_write,
                     lobby_id,
+                    name,
                 } => {
                     if let Some(lobby_sender) = self.lobby_channels.get(&lobby_id) {
                         if let Err(e) = lobby_sender
-                            .send(GameLobbyEvent::NewConnection { ws_read, ws_write })
+                            .send(GameLobbyEvent::NewConnection {
+                                ws_read,
+                                ws_write,
+                                name,
+                            })
                             .await
                         {
                             error!("Error sending user to game lobby: {:?}", e);
                         }
                     }
                 }
-                LobbyManagerEvent::CreateNewLobby { ws_read, ws_write } => {
+                LobbyManagerEvent::Reconnect {
+                    ws_read,
+                    mut ws_write,
+                    lobby_id,
+                    token,
+                } => match self.lobby_channels.get(&lobby_id) {
+                    Some(lobby_sender) => {
+                        if let Err(e) = lobby_sender
+                            .send(GameLobbyEvent::Reconnect {
+                                ws_read,
+                                ws_write,
+                                token,
+                            })
+                            .await
+                        {
+                            error!("Error sending reconnecting user to game lobby: {:?}", e);
+                        }
+                    }
+                    None => {
+                        send_logging(&mut ws_write, PacketToClient::UnknownLobbyId).await;
+                    }
+                },
+                LobbyManagerEvent::CreateNewLobby {
+                    ws_read,
+                    ws_write,
+                    name,
+                } => {
                     let new_id = generate_id(&self.lobby_channels);
                     let (mut lobby, lobby_sender) = GameLobby::new(new_id, sender.clone());
                     self.lobby_channels.insert(new_id, lobby_sender.clone());
@@ -86,7 +135,11 @@ impl LobbyManager {
                         lobby.run().await;
                     });
                     if let Err(e) = lobby_sender
-                        .send(GameLobbyEvent::NewConnection { ws_read, ws_write })
+                        .send(GameLobbyEvent::NewConnection {
+                            ws_read,
+                            ws_write,
+                            name,
+                        })
                         .await
                     {
                         error!("Error sending user to newly created game lobby: {:?}", e);
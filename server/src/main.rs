@@ -55,28 +55,41 @@ async fn run_server() -> Result<(), Error> {
                     let mut ws_read: util::WsReceiver = Box::pin(ws_read);
                     //Decide what to do with the connection based on the first received message
                     match ws_read.next().await {
-                        Some(PacketToServer::CreateNewLobby) => {
+                        Some(PacketToServer::CreateNewLobby { name }) => {
                             lobby_send
                                 .send(lobby_manager::LobbyManagerEvent::CreateNewLobby {
                                     ws_read,
                                     ws_write,
+                                    name,
                                 })
                                 .await?;
                             Ok::<(), Error>(())
                         }
-                        Some(PacketToServer::JoinLobby(lobby_id)) => {
+                        Some(PacketToServer::JoinLobby { lobby_id, name }) => {
                             lobby_send
                                 .send(lobby_manager::LobbyManagerEvent::JoinLobby {
                                     ws_read,
                                     ws_write,
                                     lobby_id,
+                                    name,
+                                })
+                                .await?;
+                            Ok(())
+                        }
+                        Some(PacketToServer::Reconnect { lobby_id, token }) => {
+                            lobby_send
+                                .send(lobby_manager::LobbyManagerEvent::Reconnect {
+                                    ws_read,
+                                    ws_write,
+                                    lobby_id,
+                                    token,
                                 })
                                 .await?;
                             Ok(())
                         }
                         Some(_) => {
                             warn!(
-                                "Received initial packet that is not a create/join lobby request"
+                                "Received initial packet that is not a create/join/reconnect lobby request"
                             );
                             Ok(())
                         }
@@ -2,7 +2,7 @@ use std::{collections::HashMap, pin::Pin};
 
 use anyhow::Error;
 use futures::{Sink, SinkExt, Stream};
-use rand::Rng;
+use rand::{distributions::Alphanumeric, Rng};
 use werewolf_rs::{
     packet::{PacketToClient, PacketToServer},
     util::Id,
@@ -25,3 +25,14 @@ pub fn generate_id<T, V>(used_ids: &HashMap<Id<T>, V>) -> Id<T> {
         }
     }
 }
+
+/*
+Generates a random secret that can be handed out to clients
+*/
+pub fn generate_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
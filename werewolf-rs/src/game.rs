@@ -11,6 +11,46 @@ pub enum Role {
     Spectator,
     Villager,
     Werewolf,
+    Seer,
+    Witch,
+    Hunter,
+    Cupid,
+    Bodyguard,
+    LittleGirl,
+    Thief,
+    VillageIdiot,
+    Elder,
+    //Hunts with the pack, but wins alone
+    WhiteWerewolf,
+    BigBadWolf,
+    //Wins alone if the village lynches them
+    Tanner,
+    SerialKiller,
+    //Becomes a werewolf once their role model dies
+    WildChild,
+    //Becomes a werewolf instead of dying when attacked by the werewolves
+    Cursed,
+    //The siblings know each other and meet at night to talk
+    Sister,
+    Brother,
+    //Learns whether a player or one of their neighbors is a werewolf, until the fox misses once
+    Fox,
+    //The village learns each morning whether a werewolf sits next to the bear tamer
+    BearTamer,
+}
+
+/*
+The teams that a player can be part of. A game ends once one of them has won.
+Each role starts out in the faction given by Role::faction, but the faction of a player can change during the game
+*/
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum Faction {
+    Village,
+    Werewolves,
+    //Two lovers from different factions, who can only win together
+    Lovers,
+    //A single player that has won alone
+    Solo,
 }
 
 /*
@@ -21,6 +61,126 @@ pub enum RoleData {
     Spectator,
     Villager,
     Werewolf,
+    Seer,
+    //Each potion can only be used once per game
+    Witch {
+        healing_potion_used: bool,
+        poison_potion_used: bool,
+    },
+    Hunter,
+    Cupid,
+    //The same player can't be protected two nights in a row
+    Bodyguard {
+        last_protected: Option<PlayerId>,
+    },
+    LittleGirl,
+    Thief,
+    //The village idiot is revealed once they have survived being lynched, and can't vote afterwards
+    VillageIdiot {
+        revealed: bool,
+    },
+    //The elder survives the first attack of the werewolves
+    Elder {
+        attacked: bool,
+    },
+    WhiteWerewolf,
+    BigBadWolf,
+    Tanner,
+    SerialKiller,
+    WildChild {
+        role_model: Option<PlayerId>,
+        transformed: bool,
+    },
+    Cursed {
+        transformed: bool,
+    },
+    Sister,
+    Brother,
+    Fox {
+        has_power: bool,
+    },
+    BearTamer,
+}
+
+/*
+The setup of a game, as chosen by the lobby host. This is visible to everyone in the lobby
+*/
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameConfig {
+    //The role cards that are dealt at the start of the game. Players without a card become villagers.
+    //If the thief is part of the game, there may be two cards more than players
+    pub roles: Vec<Role>,
+    pub durations: PhaseDurations,
+    pub tie_break_rule: TieBreakRule,
+    pub werewolf_vote_fallback: WerewolfVoteFallback,
+    pub seer_reveals: SeerReveal,
+    //Whether the village elects a mayor on the first day, whose vote counts double
+    pub elect_mayor: bool,
+    //The probability (between 0 and 1) that the werewolves catch the little girl spying on them in a night
+    pub little_girl_catch_chance: f64,
+    //The siblings wake up together in the first night and then every n-th night. They only meet once if this is None
+    pub sibling_meeting_interval: Option<u32>,
+    //Whether the role of a player is visible to everyone once the player has died
+    pub reveal_dead_roles: bool,
+}
+
+/*
+How the village decides if several players have received the most votes
+*/
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub enum TieBreakRule {
+    //Vote again, with only the tied players as candidates. If they are tied again, no one dies
+    Runoff,
+    NoLynch,
+    KillAll,
+    //Kill one of the tied players at random
+    Random,
+}
+
+/*
+The time limits of the phases of a game in seconds. A phase without a limit waits for all players.
+Once the time is up, the phase is resolved with the responses that have been received so far
+*/
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseDurations {
+    pub nomination: Option<u64>,
+    pub village_vote: Option<u64>,
+    pub werewolf_vote: Option<u64>,
+    //The night actions of roles other than the werewolves
+    pub night_action: Option<u64>,
+}
+
+/*
+How the victim of the werewolves is chosen if they haven't agreed on one when the time is up
+*/
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub enum WerewolfVoteFallback {
+    //The player with the most votes dies, unless there is a tie
+    Majority,
+    //One of the players with the most votes dies
+    RandomAmongTop,
+    NoKill,
+}
+
+/*
+What the seer learns about the player they inspect
+*/
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub enum SeerReveal {
+    Role,
+    Faction,
+}
+
+/*
+The phases a lobby cycles through: Lobby -> InGame -> PostGame -> Lobby
+*/
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum LobbyPhase {
+    //Waiting for the host to start a game
+    Lobby,
+    InGame,
+    //A game has ended, but the roles are still visible until the lobby is reset
+    PostGame,
 }
 
 /*
@@ -29,6 +189,10 @@ The information on a game that is visible to a specific player
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameInfo {
     pub players: HashMap<PlayerId, PlayerInfo>,
+    //The order in which the players sit in a circle. The last player sits next to the first one
+    pub seating: Vec<PlayerId>,
+    pub config: GameConfig,
+    pub phase: LobbyPhase,
 }
 
 /*
@@ -36,15 +200,23 @@ The information on a player that is visible to the same or a different player
 */
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayerInfo {
+    pub name: String,
     pub role_info: RoleInfo,
     pub is_alive: bool,
     pub is_lobby_host: bool,
+    //Whether the player currently has a connection to the server
+    pub is_connected: bool,
+    //The player this player is in love with. Only known to the lovers themselves and to cupid
+    pub lover: Option<PlayerId>,
+    pub is_mayor: bool,
 }
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum RoleInfo {
     Unknown,
     Known(Role),
     KnownData(RoleData),
+    //Only the side the player plays for is known, for example after an inspection by the seer
+    KnownFaction(Faction),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,6 +224,11 @@ pub enum CauseOfDeath {
     Unknown,
     VillageVote,
     Werewolves,
+    Poison,
+    HunterShot,
+    //The lover of the player has died
+    Heartbreak,
+    SerialKiller,
 }
 
 impl Role {
@@ -59,14 +236,165 @@ impl Role {
         !matches!(self, Role::Spectator)
     }
     /*
+    The faction a player with this role starts out in. Spectators are not part of any faction
+    */
+    pub fn faction(&self) -> Option<Faction> {
+        match self {
+            Self::Spectator => None,
+            Self::Villager => Some(Faction::Village),
+            Self::Werewolf => Some(Faction::Werewolves),
+            Self::Seer => Some(Faction::Village),
+            Self::Witch => Some(Faction::Village),
+            Self::Hunter => Some(Faction::Village),
+            Self::Cupid => Some(Faction::Village),
+            Self::Bodyguard => Some(Faction::Village),
+            Self::LittleGirl => Some(Faction::Village),
+            Self::Thief => Some(Faction::Village),
+            Self::VillageIdiot => Some(Faction::Village),
+            Self::Elder => Some(Faction::Village),
+            Self::WhiteWerewolf => Some(Faction::Werewolves),
+            Self::BigBadWolf => Some(Faction::Werewolves),
+            Self::Tanner => Some(Faction::Solo),
+            Self::SerialKiller => Some(Faction::Solo),
+            Self::WildChild => Some(Faction::Village),
+            Self::Cursed => Some(Faction::Village),
+            Self::Sister => Some(Faction::Village),
+            Self::Brother => Some(Faction::Village),
+            Self::Fox => Some(Faction::Village),
+            Self::BearTamer => Some(Faction::Village),
+        }
+    }
+    /*
+    Whether this role wins alone instead of with its faction
+    */
+    pub fn wins_alone(&self) -> bool {
+        matches!(self, Self::WhiteWerewolf)
+    }
+    /*
     A list of roles that have to have finished their actions for this night, before this role's turn
     */
     pub fn dependencies_in_night(&self) -> Vec<Role> {
-        match self {
+        let mut dependencies = match self {
             Self::Spectator => Vec::new(),
             Self::Villager => Vec::new(),
             Self::Werewolf => Vec::new(),
+            Self::Seer => Vec::new(),
+            //The witch has to know all victims of the night
+            Self::Witch => vec![
+                Role::Werewolf,
+                Role::WhiteWerewolf,
+                Role::BigBadWolf,
+                Role::SerialKiller,
+            ],
+            Self::Hunter => Vec::new(),
+            Self::Cupid => Vec::new(),
+            Self::Bodyguard => Vec::new(),
+            Self::LittleGirl => Vec::new(),
+            Self::Thief => Vec::new(),
+            Self::VillageIdiot => Vec::new(),
+            Self::Elder => Vec::new(),
+            //The special wolves act after the vote of the pack
+            Self::WhiteWerewolf => vec![Role::Werewolf],
+            Self::BigBadWolf => vec![Role::Werewolf],
+            Self::Tanner => Vec::new(),
+            Self::SerialKiller => Vec::new(),
+            Self::WildChild => Vec::new(),
+            Self::Cursed => Vec::new(),
+            Self::Sister => Vec::new(),
+            Self::Brother => Vec::new(),
+            Self::Fox => Vec::new(),
+            Self::BearTamer => Vec::new(),
+        };
+        //The thief has to choose their role before anyone else acts
+        if *self != Self::Thief {
+            dependencies.push(Self::Thief);
         }
+        dependencies
+    }
+    /*
+    Whether this role has a turn in the given night. The first night of a game is night 1
+    */
+    pub fn wakes_up_in_night(&self, night: u32, config: &GameConfig) -> bool {
+        match self {
+            Self::Sister | Self::Brother => match config.sibling_meeting_interval {
+                //Nights 1, 1 + interval, 1 + 2 * interval, ...
+                Some(interval) => night % interval == 1 % interval,
+                None => night == 1,
+            },
+            Self::Cupid | Self::Thief | Self::WildChild => night == 1,
+            //The second, fourth, ... night
+            Self::WhiteWerewolf => night % 2 != 1,
+            _ => true,
+        }
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            roles: Vec::new(),
+            durations: PhaseDurations::default(),
+            tie_break_rule: TieBreakRule::NoLynch,
+            werewolf_vote_fallback: WerewolfVoteFallback::Majority,
+            seer_reveals: SeerReveal::Role,
+            elect_mayor: true,
+            little_girl_catch_chance: 0.2,
+            sibling_meeting_interval: Some(2),
+            reveal_dead_roles: true,
+        }
+    }
+}
+
+impl Default for PhaseDurations {
+    fn default() -> Self {
+        PhaseDurations {
+            nomination: Some(90),
+            village_vote: Some(60),
+            werewolf_vote: Some(90),
+            night_action: Some(60),
+        }
+    }
+}
+
+impl GameConfig {
+    /*
+    Checks whether a game with this config can be played by the given number of players.
+    Returns a description of the problem otherwise
+    */
+    pub fn validate(&self, player_count: usize) -> Result<(), String> {
+        if self.roles.iter().any(|role| !role.is_player()) {
+            return Err("Spectator is not a valid role card".to_string());
+        }
+        let max_cards = if self.roles.contains(&Role::Thief) {
+            player_count + 2
+        } else {
+            player_count
+        };
+        if self.roles.len() > max_cards {
+            return Err(format!(
+                "There are {} role cards, but only {} players",
+                self.roles.len(),
+                player_count
+            ));
+        }
+        if self.sibling_meeting_interval == Some(0) {
+            return Err("The siblings can't meet more than once per night".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.little_girl_catch_chance) {
+            return Err(
+                "The chance to catch the little girl has to be between 0 and 1".to_string(),
+            );
+        }
+        let durations = [
+            self.durations.nomination,
+            self.durations.village_vote,
+            self.durations.werewolf_vote,
+            self.durations.night_action,
+        ];
+        if durations.contains(&Some(0)) {
+            return Err("A phase has to last at least one second".to_string());
+        }
+        Ok(())
     }
 }
 
@@ -76,6 +404,33 @@ impl RoleData {
             Role::Spectator => Self::Spectator,
             Role::Villager => Self::Villager,
             Role::Werewolf => Self::Werewolf,
+            Role::Seer => Self::Seer,
+            Role::Witch => Self::Witch {
+                healing_potion_used: false,
+                poison_potion_used: false,
+            },
+            Role::Hunter => Self::Hunter,
+            Role::Cupid => Self::Cupid,
+            Role::Bodyguard => Self::Bodyguard {
+                last_protected: None,
+            },
+            Role::LittleGirl => Self::LittleGirl,
+            Role::Thief => Self::Thief,
+            Role::VillageIdiot => Self::VillageIdiot { revealed: false },
+            Role::Elder => Self::Elder { attacked: false },
+            Role::WhiteWerewolf => Self::WhiteWerewolf,
+            Role::BigBadWolf => Self::BigBadWolf,
+            Role::Tanner => Self::Tanner,
+            Role::SerialKiller => Self::SerialKiller,
+            Role::WildChild => Self::WildChild {
+                role_model: None,
+                transformed: false,
+            },
+            Role::Cursed => Self::Cursed { transformed: false },
+            Role::Sister => Self::Sister,
+            Role::Brother => Self::Brother,
+            Role::Fox => Self::Fox { has_power: true },
+            Role::BearTamer => Self::BearTamer,
         }
     }
 
@@ -84,6 +439,96 @@ impl RoleData {
             Self::Spectator => Role::Spectator,
             Self::Villager => Role::Villager,
             Self::Werewolf => Role::Werewolf,
+            Self::Seer => Role::Seer,
+            Self::Witch { .. } => Role::Witch,
+            Self::Hunter => Role::Hunter,
+            Self::Cupid => Role::Cupid,
+            Self::Bodyguard { .. } => Role::Bodyguard,
+            Self::LittleGirl => Role::LittleGirl,
+            Self::Thief => Role::Thief,
+            Self::VillageIdiot { .. } => Role::VillageIdiot,
+            Self::Elder { .. } => Role::Elder,
+            Self::WhiteWerewolf => Role::WhiteWerewolf,
+            Self::BigBadWolf => Role::BigBadWolf,
+            Self::Tanner => Role::Tanner,
+            Self::SerialKiller => Role::SerialKiller,
+            Self::WildChild { .. } => Role::WildChild,
+            Self::Cursed { .. } => Role::Cursed,
+            Self::Sister => Role::Sister,
+            Self::Brother => Role::Brother,
+            Self::Fox { .. } => Role::Fox,
+            Self::BearTamer => Role::BearTamer,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_with_roles(roles: Vec<Role>) -> GameConfig {
+        GameConfig {
+            roles,
+            ..GameConfig::default()
+        }
+    }
+
+    #[test]
+    fn default_config_is_valid() {
+        assert!(GameConfig::default().validate(0).is_ok());
+    }
+
+    #[test]
+    fn more_cards_than_players_are_rejected() {
+        let config = config_with_roles(vec![Role::Werewolf, Role::Villager, Role::Villager]);
+        assert!(config.validate(3).is_ok());
+        assert!(config.validate(2).is_err());
+    }
+
+    #[test]
+    fn spectator_cards_are_rejected() {
+        let config = config_with_roles(vec![Role::Werewolf, Role::Spectator]);
+        assert!(config.validate(4).is_err());
+    }
+
+    #[test]
+    fn empty_phases_are_rejected() {
+        let mut config = GameConfig::default();
+        config.durations.werewolf_vote = Some(0);
+        assert!(config.validate(4).is_err());
+        config.durations.werewolf_vote = None;
+        assert!(config.validate(4).is_ok());
+    }
+
+    #[test]
+    fn cupid_only_wakes_up_in_the_first_night() {
+        let config = GameConfig::default();
+        assert!(Role::Cupid.wakes_up_in_night(1, &config));
+        assert!(!Role::Cupid.wakes_up_in_night(2, &config));
+    }
+
+    #[test]
+    fn white_werewolf_wakes_up_every_second_night() {
+        let config = GameConfig::default();
+        let nights: Vec<bool> = (1..=4)
+            .map(|night| Role::WhiteWerewolf.wakes_up_in_night(night, &config))
+            .collect();
+        assert_eq!(nights, vec![false, true, false, true]);
+    }
+
+    #[test]
+    fn siblings_meet_in_the_configured_interval() {
+        let meetings = |interval| {
+            let config = GameConfig {
+                sibling_meeting_interval: interval,
+                ..GameConfig::default()
+            };
+            (1..=5)
+                .filter(|night| Role::Sister.wakes_up_in_night(*night, &config))
+                .collect::<Vec<u32>>()
+        };
+        assert_eq!(meetings(None), vec![1]);
+        assert_eq!(meetings(Some(1)), vec![1, 2, 3, 4, 5]);
+        assert_eq!(meetings(Some(2)), vec![1, 3, 5]);
+    }
+}
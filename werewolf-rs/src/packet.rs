@@ -1,8 +1,9 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 use crate::{
-    game::{CauseOfDeath, GameInfo, Role},
+    game::{CauseOfDeath, Faction, GameConfig, GameInfo, Role, RoleData, RoleInfo, TieBreakRule},
     util::{InteractionId, LobbyId, PlayerId},
 };
 
@@ -18,9 +19,28 @@ pub fn deserialize_packet<'a, P: Deserialize<'a>>(raw: &'a str) -> Result<P> {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub enum PacketToServer {
-    CreateNewLobby,
-    JoinLobby(LobbyId),
+    //Create a new lobby and join it with the given nickname
+    CreateNewLobby {
+        name: String,
+    },
+    JoinLobby {
+        lobby_id: LobbyId,
+        name: String,
+    },
+    //Take over an existing player after the connection has been lost, using the token from JoinedLobby
+    Reconnect {
+        lobby_id: LobbyId,
+        token: String,
+    },
+    //Change the nickname of this client
+    SetName(String),
     StartGame,
+    //Stop the running game and return to the lobby. This is only allowed for the lobby host
+    CancelGame,
+    //Reset the roles of a finished game so that the next one can be prepared. This is only allowed for the lobby host
+    ReturnToLobby,
+    //Replace the game config of the lobby. This is only allowed for the lobby host
+    UpdateGameConfig(GameConfig),
     //A response to an interaction that was created by the server. One interaction may be responded to several times, depending on its type
     InteractionResponse {
         interaction_id: InteractionId,
@@ -30,15 +50,35 @@ pub enum PacketToServer {
     Unknown,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub enum PacketToClient {
     UnknownLobbyId,
+    //A request of this client could not be fulfilled
+    RequestRejected(RequestError),
     JoinedLobby {
         lobby_id: LobbyId,
         client_id: PlayerId,
+        //A secret that allows the client to reconnect as the same player. This is sent again after every reconnect
+        reconnect_token: String,
     },
     GameUpdate(GameInfo),
-    PlayerDied(PlayerId, CauseOfDeath, Role),
+    //The role of the player is only included if the roles of dead players are revealed
+    PlayerDied(PlayerId, CauseOfDeath, Option<Role>),
+    //Public news for the whole village at the begin of the day
+    MorningAnnouncement(Announcement),
+    //This client has been converted to another faction, for example because the cursed villager was attacked by the werewolves
+    FactionChanged(Faction),
+    //The outcome of the village vote, including the rule that was used if the vote was tied
+    VillageVoteResult {
+        lynched_players: Vec<PlayerId>,
+        tie_break_rule: Option<TieBreakRule>,
+    },
+    //The game has ended. All roles are revealed to every client
+    GameOver {
+        winner: Faction,
+        winning_players: Vec<PlayerId>,
+        roles: HashMap<PlayerId, RoleData>,
+    },
     //The begin of an interaction (a series of packets that are linked by an ID)
     InteractionRequest {
         interaction_id: InteractionId,
@@ -57,8 +97,32 @@ pub enum PacketToClient {
     Unknown,
 }
 
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub enum RequestError {
+    //Only the lobby host is allowed to do this
+    NotLobbyHost,
+    InvalidGameConfig(String),
+    GameAlreadyRunning,
+    NoGameRunning,
+    EmptyName,
+    //Another player in the lobby already uses this name
+    DuplicateName,
+    InvalidReconnectToken,
+}
+
+/*
+An announcement to all players in the morning, after the deaths of the night have been revealed
+*/
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub enum Announcement {
+    //The bear growls if one of the living neighbors of the bear tamer is a werewolf
+    BearGrowls { bear_tamer: PlayerId, growls: bool },
+}
+
 /*
-The data that can be part of an interation. The interactions are:
+The data that can be part of an interation.
+Deadlines are unix timestamps in milliseconds, after which the server resolves the interaction with the responses it has received.
+The interactions are:
 
 - NominationVote (Nv)
     Each client can nominate a player (but doesn't have to).
@@ -66,18 +130,96 @@ The data that can be part of an interation. The interactions are:
     There has to be at least one nomination before anyone can choose to nominate no one.
 - WerewolfVote (Wv)
     Each werewolf can vote for a player (and change their vote at any time).
-    Once all werewolves vote for the same player, they can lock in their choice to end the vote
+    Once all werewolves vote for the same player, they can lock in their choice to end the vote.
+    If they haven't agreed when the time is up, the fallback from the game config decides
+- TargetSelection (Ts)
+    A single player chooses one of the selectable players (or no one) as the target of an action.
+    The purpose describes what the target is chosen for
+- CardSelection (Cs)
+    A single player chooses one of several role cards (or none, unless the choice is mandatory)
+- GroupChat (Gc)
+    A group of players can send messages to each other. The chat ends once every member is done
 */
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub enum InteractionRequest {
     NvBegin {
+        purpose: VotePurpose,
         nominatable_players: Vec<PlayerId>,
         can_vote: bool,
+        //The end of the nomination phase
+        deadline: Option<u64>,
     },
     WvBegin {
         selectable_players: Vec<PlayerId>,
         can_vote: bool,
+        deadline: Option<u64>,
+    },
+    TsBegin {
+        purpose: TargetPurpose,
+        selectable_players: Vec<PlayerId>,
+        deadline: Option<u64>,
+    },
+    CsBegin {
+        cards: Vec<Role>,
+        mandatory: bool,
+        deadline: Option<u64>,
     },
+    GcBegin {
+        members: Vec<PlayerId>,
+        deadline: Option<u64>,
+    },
+}
+/*
+What a nomination vote is held for
+*/
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub enum VotePurpose {
+    Lynch,
+    //A second vote between the players that were tied in the lynch vote. There are no nominations in a runoff
+    Runoff,
+    //The election of the mayor on the first day
+    MayorElection,
+}
+/*
+How the werewolf vote has been decided
+*/
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub enum WvResolution {
+    //All werewolves voted for the same player
+    Agreed,
+    //The time ran out and the player with the most votes was chosen (if there was one)
+    Majority,
+    //The time ran out and one of the players with the most votes was chosen at random
+    Random,
+    NoKill,
+    //The werewolves caught the little girl spying on them, so she is their victim
+    LittleGirlCaught,
+}
+/*
+What a target selection is held for
+*/
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub enum TargetPurpose {
+    SeerInspection,
+    //The selectable players are the ones that are going to die this night
+    WitchHeal,
+    WitchPoison,
+    HunterShot,
+    CupidFirstLover,
+    //The first lover can't be selected again
+    CupidSecondLover,
+    BodyguardProtection,
+    //The dying mayor names the next mayor
+    MayorSuccessor,
+    //The player whose death turns the wild child into a werewolf
+    WildChildRoleModel,
+    //The fox learns whether the target or one of their neighbors is a werewolf
+    FoxSniff,
+    //The white werewolf may kill another werewolf every second night
+    WhiteWerewolfKill,
+    //The extra victim of the big bad wolf
+    BigBadWolfKill,
+    SerialKillerKill,
 }
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub enum InteractionResponse {
@@ -85,6 +227,11 @@ pub enum InteractionResponse {
     NvVote { player: PlayerId },
     WvVote { vote: PlayerId },
     WvLockVote,
+    TsSelect { target: Option<PlayerId> },
+    //The index of the chosen card
+    CsSelect { card: Option<usize> },
+    GcMessage { message: String },
+    GcDone,
 }
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub enum InteractionFollowup {
@@ -92,20 +239,50 @@ pub enum InteractionFollowup {
         nominated_player: Option<PlayerId>,
         nominated_by: PlayerId,
     },
-    NvNominationsFinished,
+    NvNominationsFinished {
+        //The end of the voting phase
+        deadline: Option<u64>,
+    },
     NvVoteFinished {
         //(voter, vote) tuples for all votes
         votes: Vec<(PlayerId, PlayerId)>,
     },
+    //The voter is hidden from the little girl
     WvNewVote {
         vote: PlayerId,
-        voted_by: PlayerId,
+        voted_by: Option<PlayerId>,
     },
     WvLockedVote {
         vote: PlayerId,
-        voted_by: PlayerId,
+        voted_by: Option<PlayerId>,
     },
     WvVoteFinished {
         vote: Option<PlayerId>,
+        resolution: WvResolution,
+    },
+    //The result of the inspection of the seer. This is also visible in the GameInfo afterwards
+    TsSeerResult {
+        target: PlayerId,
+        role_info: RoleInfo,
+    },
+    //Whether the target of the fox or one of their living neighbors is a werewolf
+    TsFoxResult {
+        target: PlayerId,
+        werewolf_nearby: bool,
+    },
+    //A participant lost its connection and is skipped until it reconnects
+    PlayerAbsent {
+        player: PlayerId,
+    },
+    PlayerReturned {
+        player: PlayerId,
+    },
+    GcNewMessage {
+        message: String,
+        sent_by: PlayerId,
+    },
+    GcMemberDone {
+        player: PlayerId,
     },
+    GcFinished,
 }